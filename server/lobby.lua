@@ -0,0 +1,52 @@
+-- private lobby codes for skill-duel. drop this in the nakama server's modules directory.
+-- a lobby is a relayed match, these only turn its long id into a short code and back
+
+local nk = require("nakama")
+
+-- no letters that read like numbers, and none the game's menus move around with (w, a, s, d)
+local LETTERS = "BCEFGHJKLMNPQRTUVXYZ23456789"
+local LENGTH = 6
+local COLLECTION = "lobbies"
+
+local function read(code)
+  local objects = nk.storage_read({ { collection = COLLECTION, key = code } })
+  return objects[1]
+end
+
+-- takes a match id, gives back a code nobody else is using for it
+local function register(_, match_id)
+  for _ = 1, 20 do
+    local code = ""
+    for _ = 1, LENGTH do
+      local index = math.random(#LETTERS)
+      code = code .. LETTERS:sub(index, index)
+    end
+
+    -- a code whose match has ended is free again
+    local existing = read(code)
+    if existing == nil or nk.match_get(existing.value.match_id) == nil then
+      nk.storage_write({ {
+        collection = COLLECTION,
+        key = code,
+        value = { match_id = match_id },
+        permission_read = 0,
+        permission_write = 0,
+      } })
+      return code
+    end
+  end
+
+  error("no free lobby codes")
+end
+
+-- takes a code, gives back the match id if that lobby's still open
+local function find(_, code)
+  local existing = read(string.upper(code))
+  if existing == nil or nk.match_get(existing.value.match_id) == nil then
+    error("no lobby with that code")
+  end
+  return existing.value.match_id
+end
+
+nk.register_rpc(register, "lobby_register")
+nk.register_rpc(find, "lobby_find")
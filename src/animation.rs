@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+
+use macroquad::prelude::*;
+use serde::Deserialize;
+
+use crate::WeaponType;
+
+fn one() -> f32 {
+    1.0
+}
+
+//one step of a clip. everything but the time is optional, see clips.toml
+#[derive(Clone, Deserialize)]
+pub struct Frame {
+    //none keeps whatever region the sprite is drawn with
+    #[serde(default)]
+    pub region: Option<String>,
+    pub seconds: f32,
+    #[serde(default)]
+    pub offset: (f32, f32),
+    #[serde(default)]
+    pub rotation: f32,
+    #[serde(default = "one")]
+    pub scale: f32,
+    #[serde(default = "one")]
+    pub alpha: f32,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct Clip {
+    #[serde(default)]
+    pub looped: bool,
+    pub frames: Vec<Frame>,
+}
+
+impl Clip {
+    fn length(&self) -> f32 {
+        self.frames.iter().map(|frame| frame.seconds).sum()
+    }
+}
+
+//what pack_atlas writes next to the sheet
+#[derive(Deserialize)]
+struct Index {
+    regions: HashMap<String, [f32; 4]>,
+}
+
+//the sprite sheet, where everything is on it and the clips that play over it
+#[derive(Clone)]
+pub struct Sprites {
+    texture: Texture2D,
+    regions: HashMap<String, Rect>,
+    clips: HashMap<String, Clip>,
+}
+
+impl Sprites {
+    //draws nothing, for running without a window
+    pub fn empty() -> Self {
+        Self {
+            texture: Texture2D::empty(),
+            regions: HashMap::new(),
+            clips: HashMap::new(),
+        }
+    }
+
+    pub fn parse(texture: Texture2D, index: &str, clips: &str) -> Result<Self, String> {
+        let index: Index = toml::from_str(index).map_err(|error| format!("atlas.toml: {}", error))?;
+        let clips: HashMap<String, Clip> = toml::from_str(clips).map_err(|error| format!("clips.toml: {}", error))?;
+
+        Ok(Self {
+            texture,
+            regions: index.regions.into_iter().map(|(name, [x, y, w, h])| (name, Rect::new(x, y, w, h))).collect(),
+            clips,
+        })
+    }
+
+    //where the animator is in its clip, none once a one shot clip has played out or if it doesn't exist
+    pub fn frame(&self, animator: &Animator) -> Option<&Frame> {
+        let clip = self.clips.get(animator.clip)?;
+        let mut time = if clip.looped {animator.time%clip.length().max(f32::EPSILON)} else {animator.time};
+
+        for frame in clip.frames.iter() {
+            if time < frame.seconds {
+                return Some(frame);
+            }
+            time -= frame.seconds;
+        }
+
+        None
+    }
+
+    pub fn finished(&self, animator: &Animator) -> bool {
+        self.frame(animator).is_none()
+    }
+
+    //takes the same arguments as draw_texture_ex but for one region of the sheet, moved about by the frame
+    pub fn draw(&self, region: &str, frame: Option<&Frame>, x: f32, y: f32, color: Color, params: DrawTextureParams) {
+        let region = frame.and_then(|frame| frame.region.as_deref()).unwrap_or(region);
+        let source = match self.regions.get(region) {
+            Some(source) => *source,
+            None => return,
+        };
+        let (offset, rotation, scale, alpha) = frame.map_or(((0.0, 0.0), 0.0, 1.0, 1.0), |frame| (frame.offset, frame.rotation, frame.scale, frame.alpha));
+
+        //scaled about the middle so a frame can swell without sliding
+        let size = params.dest_size.unwrap_or(Vec2::new(source.w, source.h));
+        let scaled = size*scale;
+        draw_texture_ex(
+            self.texture,
+            x+offset.0-(scaled.x-size.x)/2.0,
+            y+offset.1-(scaled.y-size.y)/2.0,
+            Color::new(color.r, color.g, color.b, color.a*alpha),
+            DrawTextureParams { source: Some(source), dest_size: Some(scaled), rotation: params.rotation+rotation, ..params },
+        );
+    }
+}
+
+pub fn weapon_region(weapon: WeaponType) -> &'static str {
+    match weapon {
+        WeaponType::Knife(_) => "knife",
+        WeaponType::Gunner => "gunner",
+        WeaponType::Grenade => "launcher",
+        WeaponType::Shotgun => "shotgun",
+        WeaponType::Sprayer => "sprayer",
+        WeaponType::Sniper => "sniper",
+    }
+}
+
+//plays one clip at a time. it runs on seconds rather than ticks so it looks the same at any frame rate
+#[derive(Clone, Copy)]
+pub struct Animator {
+    clip: &'static str,
+    time: f32,
+}
+
+impl Animator {
+    pub fn new(clip: &'static str) -> Self {
+        Self {
+            clip,
+            time: 0.0,
+        }
+    }
+
+    pub fn clip(&self) -> &'static str {
+        self.clip
+    }
+
+    //switches clip, carrying on if it's already the one playing
+    pub fn play(&mut self, clip: &'static str) {
+        if self.clip != clip {
+            self.restart(clip);
+        }
+    }
+
+    pub fn restart(&mut self, clip: &'static str) {
+        self.clip = clip;
+        self.time = 0.0;
+    }
+
+    pub fn advance(&mut self, seconds: f32) {
+        self.time += seconds;
+    }
+}
+
+//everything animating on one player. the renderer keeps these by player id, the match never sees them
+#[derive(Clone, Copy)]
+pub struct PlayerAnimation {
+    pub body: Animator,
+    pub weapon: Animator,
+    pub flash: Option<Animator>,
+    //facing when last drawn, for the death clip
+    pub direction: f32,
+}
+
+impl PlayerAnimation {
+    pub fn new() -> Self {
+        Self {
+            body: Animator::new("idle"),
+            weapon: Animator::new("weapon_idle"),
+            flash: None,
+            direction: 0.0,
+        }
+    }
+
+    //fire and swap play through once before the body goes back to what the player is doing
+    pub fn update(&mut self, sprites: &Sprites, state: &'static str, seconds: f32) {
+        for animator in [&mut self.body, &mut self.weapon] {
+            animator.advance(seconds);
+        }
+        if let Some(ref mut flash) = self.flash {
+            flash.advance(seconds);
+        }
+
+        if !matches!(self.body.clip(), "fire" | "swap") || sprites.finished(&self.body) {
+            self.body.play(state);
+        }
+        if sprites.finished(&self.weapon) {
+            self.weapon.play("weapon_idle");
+        }
+        if self.flash.is_some_and(|flash| sprites.finished(&flash)) {
+            self.flash = None;
+        }
+    }
+}
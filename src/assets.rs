@@ -0,0 +1,135 @@
+use std::env;
+use std::fs;
+use std::panic;
+use std::path::PathBuf;
+
+use macroquad::prelude::*;
+
+use crate::animation::Sprites;
+
+//points at a folder of replacement pngs, atlas.toml or clips.toml with the same file names, anything not in it uses the built in copy
+const OVERRIDE_VARIABLE: &str = "SKILL_DUEL_ASSETS";
+
+//players, weapons and anything else that animates is on the atlas, see animation.rs
+#[derive(Clone, Copy, PartialEq)]
+pub enum TextureId {
+    Atlas,
+    Slot,
+    GameOver,
+    PlayButton,
+    Logo,
+}
+
+const TEXTURES: [TextureId; 5] = [TextureId::Atlas, TextureId::Slot, TextureId::GameOver, TextureId::PlayButton, TextureId::Logo];
+
+impl TextureId {
+    fn file(&self) -> &'static str {
+        match self {
+            TextureId::Atlas => "atlas.png",
+            TextureId::Slot => "slot.png",
+            TextureId::GameOver => "game_over.png",
+            TextureId::PlayButton => "play_button.png",
+            TextureId::Logo => "logo.png",
+        }
+    }
+
+    //built into the binary so it runs from anywhere
+    fn embedded(&self) -> &'static [u8] {
+        match self {
+            TextureId::Atlas => include_bytes!("assets/atlas.png"),
+            TextureId::Slot => include_bytes!("assets/slot.png"),
+            TextureId::GameOver => include_bytes!("assets/game_over.png"),
+            TextureId::PlayButton => include_bytes!("assets/play_button.png"),
+            TextureId::Logo => include_bytes!("assets/logo.png"),
+        }
+    }
+}
+
+//every texture, loaded once at startup. cheap to copy into each match
+#[derive(Clone, Copy)]
+pub struct Textures([Texture2D; 5]);
+
+impl Textures {
+    //blank stand ins for running without a window
+    pub fn empty() -> Self {
+        Self([Texture2D::empty(); 5])
+    }
+
+    pub fn get(&self, id: TextureId) -> Texture2D {
+        self.0[id as usize]
+    }
+}
+
+pub struct Assets {
+    pub textures: Textures,
+    pub sprites: Sprites,
+    //what couldn't be loaded, shown on screen for a while after startup
+    pub missing: Vec<String>,
+}
+
+impl Assets {
+    pub fn load() -> Self {
+        let mut assets = Self {
+            textures: Textures::empty(),
+            sprites: Sprites::empty(),
+            missing: Vec::new(),
+        };
+
+        let directory = env::var(OVERRIDE_VARIABLE).ok().map(PathBuf::from);
+        if let Some(ref directory) = directory {
+            if !directory.is_dir() {
+                assets.missing.push(format!("{} isn't a folder", directory.display()));
+            }
+        }
+
+        for id in TEXTURES.iter() {
+            let replacement = directory.as_ref().and_then(|directory| fs::read(directory.join(id.file())).ok());
+
+            let image = match replacement.map(|bytes| decode(&bytes)) {
+                Some(Some(image)) => Some(image),
+                Some(None) => {
+                    assets.missing.push(format!("{} couldn't be read, using the built in one", id.file()));
+                    decode(id.embedded())
+                },
+                None => decode(id.embedded()),
+            };
+
+            assets.textures.0[*id as usize] = match image {
+                Some(image) => Texture2D::from_image(&image),
+                None => {
+                    assets.missing.push(format!("{} is missing", id.file()));
+                    Texture2D::from_rgba8(1, 1, &[255, 0, 255, 255])
+                },
+            };
+        }
+
+        let index = text(&directory, "atlas.toml", include_str!("assets/atlas.toml"));
+        let clips = text(&directory, "clips.toml", include_str!("assets/clips.toml"));
+        match Sprites::parse(assets.textures.get(TextureId::Atlas), &index, &clips) {
+            Ok(sprites) => assets.sprites = sprites,
+            Err(error) => assets.missing.push(error),
+        }
+
+        assets
+    }
+
+    pub fn draw_missing(&self, alpha: f32) {
+        for (index, line) in self.missing.iter().enumerate() {
+            draw_text(line, 10.0, screen_height()-20.0-index as f32*24.0, 24.0, Color::new(1.0, 0.3, 0.3, alpha));
+        }
+    }
+}
+
+fn text(directory: &Option<PathBuf>, file: &str, embedded: &str) -> String {
+    directory.as_ref().and_then(|directory| fs::read_to_string(directory.join(file)).ok()).unwrap_or_else(|| embedded.to_string())
+}
+
+//the image decoder panics on bad data instead of returning an error
+fn decode(bytes: &[u8]) -> Option<Image> {
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let image = panic::catch_unwind(|| Image::from_file_with_format(bytes, Some(ImageFormat::Png))).ok();
+    panic::set_hook(hook);
+
+    image
+}
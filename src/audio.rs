@@ -0,0 +1,360 @@
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+use macroquad::audio::{load_sound_from_bytes, play_sound, stop_sound, PlaySoundParams, Sound};
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::events::GameEvent;
+use crate::{Class, Entity, WeaponType};
+
+const SAMPLE_RATE: u32 = 22050;
+//nothing further than this from the listener gets played
+const HEARING_RANGE: f32 = 2200.0;
+//how far to one side something has to be before it leans into that ear
+const PAN_OFFSET: f32 = 300.0;
+//distance walked between footsteps, anything faster than STEP_SPEED a tick is a dash or knockback and stays quiet
+const STEP_LENGTH: f32 = 140.0;
+const STEP_SPEED: f32 = 20.0;
+
+//each category scales with master
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Volumes {
+    pub master: f32,
+    pub sfx: f32,
+    pub music: f32,
+    pub ui: f32,
+}
+
+impl Default for Volumes {
+    fn default() -> Self {
+        Self {
+            master: 1.0,
+            sfx: 0.8,
+            music: 0.4,
+            ui: 0.8,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Cue {
+    Sniper,
+    Gunner,
+    Shotgun,
+    Slug,
+    Sprayer,
+    Vent,
+    Swing,
+    Throw,
+    Hit,
+    Kill,
+    Beep,
+    Explosion,
+    Pickup,
+    Gold,
+    Step,
+    Ricochet,
+    Switch,
+    Click,
+}
+
+//in declaration order so a cue's index lines up with its sounds
+const CUES: [Cue; 18] = [Cue::Sniper, Cue::Gunner, Cue::Shotgun, Cue::Slug, Cue::Sprayer, Cue::Vent, Cue::Swing, Cue::Throw, Cue::Hit, Cue::Kill, Cue::Beep, Cue::Explosion, Cue::Pickup, Cue::Gold, Cue::Step, Cue::Ricochet, Cue::Switch, Cue::Click];
+
+//every sound is synthesised at startup, there are no audio files to ship.
+//macroquad can only set a sound's volume, not its balance, so each one is baked three times
+//leaning left, centred and leaning right and the closest fit is played
+pub struct Audio {
+    pub volumes: Volumes,
+    //a cue that couldn't be loaded stays silent
+    sounds: Vec<Option<[Sound; 3]>>,
+    music: Option<Sound>,
+    //where each player was last frame and how far they've walked since their last footstep
+    strides: HashMap<u32, (Vec2, f32)>,
+}
+
+impl Audio {
+    //plays nothing, for when there's no window or sound device
+    pub fn silent(volumes: Volumes) -> Self {
+        Self {
+            volumes,
+            sounds: Vec::new(),
+            music: None,
+            strides: HashMap::new(),
+        }
+    }
+
+    //anything that fails is left silent and noted in missing, alongside the assets that didn't load
+    pub async fn load(volumes: Volumes, missing: &mut Vec<String>) -> Self {
+        let mut audio = Self::silent(volumes);
+
+        for cue in CUES.iter() {
+            let samples = synth(*cue);
+            let sides = (
+                load_sound_from_bytes(&wav(&samples, 1.0, 0.35)).await,
+                load_sound_from_bytes(&wav(&samples, 0.8, 0.8)).await,
+                load_sound_from_bytes(&wav(&samples, 0.35, 1.0)).await,
+            );
+            audio.sounds.push(match sides {
+                (Ok(left), Ok(centre), Ok(right)) => Some([left, centre, right]),
+                _ => {
+                    missing.push(format!("the {:?} sound couldn't be loaded", cue));
+                    None
+                },
+            });
+        }
+        audio.music = load_sound_from_bytes(&wav(&music(), 0.8, 0.8)).await.ok();
+        if audio.music.is_none() {
+            missing.push("the music couldn't be loaded".to_string());
+        }
+
+        audio
+    }
+
+    fn play(&self, cue: Cue, side: usize, volume: f32) {
+        if let Some(Some(sound)) = self.sounds.get(cue as usize) {
+            play_sound(sound[side], PlaySoundParams { looped: false, volume: volume*self.volumes.master });
+        }
+    }
+
+    //something happening in the world, quieter the further it is from the listener
+    pub fn play_at(&self, cue: Cue, position: Vec2, listener: Vec2, volume: f32) {
+        let offset = position-listener;
+        let falloff = 1.0-offset.length()/HEARING_RANGE;
+        if falloff <= 0.0 {
+            return;
+        }
+
+        let side = if offset.x < -PAN_OFFSET {0} else if offset.x > PAN_OFFSET {2} else {1};
+        self.play(cue, side, volume*falloff*self.volumes.sfx);
+    }
+
+    //something happening to us, always centred and at full volume
+    pub fn play_sfx(&self, cue: Cue, volume: f32) {
+        self.play(cue, 1, volume*self.volumes.sfx);
+    }
+
+    pub fn play_ui(&self, cue: Cue) {
+        self.play(cue, 1, self.volumes.ui);
+    }
+
+    pub fn start_music(&self) {
+        if let Some(music) = self.music {
+            play_sound(music, PlaySoundParams { looped: true, volume: self.volumes.music*self.volumes.master });
+        }
+    }
+
+    pub fn stop_music(&self) {
+        if let Some(music) = self.music {
+            stop_sound(music);
+        }
+    }
+
+    //local is the id of the player we're listening as, their hits and kills get a confirmation instead of a distant thud
+    pub fn events(&self, events: &[GameEvent], listener: Vec2, local: u32) {
+        for event in events.iter() {
+            match *event {
+                GameEvent::ShotFired { weapon, alt, position, .. } => {
+                    let cue = match (weapon, alt) {
+                        (WeaponType::Knife(_), false) => Cue::Swing,
+                        (WeaponType::Knife(_), true) | (WeaponType::Grenade, _) => Cue::Throw,
+                        (WeaponType::Sniper, _) => Cue::Sniper,
+                        (WeaponType::Gunner, _) => Cue::Gunner,
+                        (WeaponType::Shotgun, false) => Cue::Shotgun,
+                        (WeaponType::Shotgun, true) => Cue::Slug,
+                        (WeaponType::Sprayer, false) => Cue::Sprayer,
+                        (WeaponType::Sprayer, true) => Cue::Vent,
+                    };
+                    self.play_at(cue, position, listener, 1.0);
+                },
+
+                GameEvent::Hit { attacker, position, .. } => {
+                    if attacker == Some(local) {
+                        self.play_sfx(Cue::Hit, 0.8);
+                    } else {
+                        self.play_at(Cue::Hit, position, listener, 0.5);
+                    }
+                },
+
+                GameEvent::Kill { killer, position, .. } => {
+                    if killer == Some(local) {
+                        self.play_sfx(Cue::Kill, 1.0);
+                    } else {
+                        self.play_at(Cue::Kill, position, listener, 0.6);
+                    }
+                },
+
+                GameEvent::GoldCollected { player, .. } => {
+                    if player == local {
+                        self.play_sfx(Cue::Gold, 0.5);
+                    }
+                },
+
+                GameEvent::PickupTaken { position, .. } => self.play_at(Cue::Pickup, position, listener, 1.0),
+
+                GameEvent::Explosion { position } => self.play_at(Cue::Explosion, position, listener, 1.0),
+
+                GameEvent::WallHit { position } => self.play_at(Cue::Ricochet, position, listener, 0.4),
+
+                GameEvent::WeaponSwitched { player, .. } => {
+                    if player == local {
+                        self.play_ui(Cue::Switch);
+                    }
+                },
+
+                //only worth hearing when it's someone else
+                GameEvent::Paused { player, paused } => {
+                    if player != local {
+                        self.play_ui(if paused {Cue::Click} else {Cue::Switch});
+                    }
+                },
+            }
+        }
+    }
+
+    //sounds that come from what's on the field rather than from events, fuses ticking and footsteps
+    pub fn world(&mut self, entities: &[Entity], listener: Vec2) {
+        let mut seen = Vec::new();
+
+        for entity in entities.iter() {
+            match entity.class {
+                Class::Grenade { fuse, .. } => {
+                    //beeps faster as it gets close to going off
+                    let every = if fuse < 40 {8} else {30};
+                    if fuse > 0 && fuse % every == 0 {
+                        self.play_at(Cue::Beep, entity.position, listener, 0.6);
+                    }
+                },
+
+                Class::Player { id, .. } => {
+                    seen.push(id);
+                    let (last, walked) = self.strides.entry(id).or_insert((entity.position, 0.0));
+                    let moved = last.distance(entity.position);
+                    *last = entity.position;
+                    if moved < STEP_SPEED {
+                        *walked += moved;
+                    }
+
+                    if *walked > STEP_LENGTH {
+                        *walked = 0.0;
+                        self.play_at(Cue::Step, entity.position, listener, 0.4);
+                    }
+                },
+
+                _ => {},
+            }
+        }
+
+        self.strides.retain(|id, _| seen.contains(id));
+    }
+}
+
+//a small xorshift so the noise comes out the same every run without touching the game's random numbers
+struct Noise(u32);
+
+impl Noise {
+    fn next(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0 as f32/u32::MAX as f32*2.0-1.0
+    }
+}
+
+fn render(length: f32, mut sample: impl FnMut(f32) -> f32) -> Vec<f32> {
+    (0..(length*SAMPLE_RATE as f32) as usize).map(|index| sample(index as f32/SAMPLE_RATE as f32)).collect()
+}
+
+fn sine(frequency: f32, t: f32) -> f32 {
+    (2.0*PI*frequency*t).sin()
+}
+
+fn square(frequency: f32, t: f32) -> f32 {
+    if (frequency*t).fract() < 0.5 {1.0} else {-1.0}
+}
+
+//low is a one pole lowpass over the noise, the smaller the step the duller the sound
+fn synth(cue: Cue) -> Vec<f32> {
+    let mut noise = Noise(0x2545f491);
+    let mut low = 0.0;
+
+    match cue {
+        Cue::Sniper => render(0.35, |t| (noise.next()*0.8+sine(90.0, t)*0.6)*(-t*18.0).exp()),
+        Cue::Gunner => render(0.12, |t| (noise.next()*0.6+square(180.0, t)*0.3)*(-t*40.0).exp()),
+        Cue::Shotgun => render(0.4, |t| {
+            low += (noise.next()-low)*0.3;
+            low*1.6*(-t*10.0).exp()
+        }),
+        Cue::Slug => render(0.45, |t| {
+            low += (noise.next()-low)*0.15;
+            (low*2.0+sine(60.0, t)*0.5)*(-t*8.0).exp()
+        }),
+        Cue::Sprayer => render(0.05, |t| noise.next()*0.4*(-t*80.0).exp()),
+        Cue::Vent => render(0.6, |t| {
+            let hiss = noise.next();
+            low += (hiss-low)*0.5;
+            (hiss-low)*0.8*(PI*t/0.6).sin()
+        }),
+        Cue::Swing => render(0.18, |t| {
+            low += (noise.next()-low)*(0.05+t*2.0);
+            low*2.0*(PI*t/0.18).sin()
+        }),
+        Cue::Throw => render(0.15, |t| sine(600.0-1000.0*t, t)*0.3*(1.0-t/0.15)),
+        Cue::Hit => render(0.08, |t| sine(880.0, t)*0.5*(-t*50.0).exp()),
+        Cue::Kill => render(0.3, |t| sine(if t < 0.12 {660.0} else {990.0}, t)*0.5*(1.0-t/0.3)),
+        Cue::Beep => render(0.05, |t| sine(1500.0, t)*0.3),
+        Cue::Explosion => render(1.2, |t| {
+            low += (noise.next()-low)*0.08;
+            (low*3.0+sine(50.0, t)*0.6)*(-t*4.0).exp()
+        }),
+        Cue::Pickup => render(0.21, |t| sine([523.25, 659.25, 783.99][((t/0.07) as usize).min(2)], t)*0.4),
+        Cue::Gold => render(0.1, |t| (sine(1320.0, t)+sine(1760.0, t))*0.2*(-t*30.0).exp()),
+        Cue::Step => render(0.06, |t| {
+            low += (noise.next()-low)*0.2;
+            low*1.2*(-t*60.0).exp()
+        }),
+        Cue::Ricochet => render(0.12, |t| sine(2000.0-3500.0*t, t)*0.3*(-t*30.0).exp()),
+        Cue::Switch => render(0.03, |t| square(400.0, t)*0.2),
+        Cue::Click => render(0.05, |t| sine(700.0, t)*0.4*(1.0-t/0.05)),
+    }
+}
+
+//four slow chords, two seconds each, looped under a match
+fn music() -> Vec<f32> {
+    let chords = [[110.0, 164.81], [87.31, 130.81], [130.81, 196.0], [98.0, 146.83]];
+    render(8.0, |t| {
+        let chord = chords[(t/2.0) as usize % 4];
+        let swell = (PI*(t%2.0)/2.0).sin();
+        (sine(chord[0], t)+sine(chord[1], t))*0.15*swell
+    })
+}
+
+//16 bit stereo pcm, left and right are the gain on each side
+fn wav(samples: &[f32], left: f32, right: f32) -> Vec<u8> {
+    let data = samples.len() as u32*4;
+    let mut bytes = Vec::with_capacity(44+data as usize);
+
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36+data).to_le_bytes());
+    bytes.extend_from_slice(b"WAVE");
+    bytes.extend_from_slice(b"fmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&2u16.to_le_bytes());
+    bytes.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    bytes.extend_from_slice(&(SAMPLE_RATE*4).to_le_bytes());
+    bytes.extend_from_slice(&4u16.to_le_bytes());
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data.to_le_bytes());
+
+    for sample in samples.iter() {
+        for gain in [left, right].iter() {
+            bytes.extend_from_slice(&(((sample*gain).clamp(-1.0, 1.0)*i16::MAX as f32) as i16).to_le_bytes());
+        }
+    }
+
+    bytes
+}
@@ -0,0 +1,78 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use macroquad::prelude::*;
+
+//packs sprite pngs into one sheet and writes an index of where each one ended up.
+//  cargo run --bin pack_atlas                              repacks the built in sprites
+//  cargo run --bin pack_atlas -- out/atlas a.png b.png     writes out/atlas.png and out/atlas.toml
+const OUTPUT: &str = "src/assets/atlas";
+const SPRITES: [&str; 9] = [
+    "src/assets/player.png",
+    "src/assets/knife.png",
+    "src/assets/gunner.png",
+    "src/assets/launcher.png",
+    "src/assets/shotgun.png",
+    "src/assets/sprayer.png",
+    "src/assets/sniper.png",
+    "src/assets/grenade.png",
+    "src/assets/muzzle_flash.png",
+];
+const WIDTH: usize = 256;
+//gap between sprites so filtering doesn't bleed one into the next
+const PADDING: usize = 2;
+
+fn main() {
+    let arguments: Vec<String> = env::args().skip(1).collect();
+    let (output, inputs) = match arguments.split_first() {
+        Some((output, inputs)) if !inputs.is_empty() => (output.clone(), inputs.to_vec()),
+        _ => (OUTPUT.to_string(), SPRITES.iter().map(|path| path.to_string()).collect()),
+    };
+
+    let mut sprites: Vec<(String, Image)> = inputs.iter().map(|path| {
+        let bytes = fs::read(path).unwrap_or_else(|error| panic!("couldn't read {}: {}", path, error));
+        let name = Path::new(path).file_stem().unwrap().to_string_lossy().to_string();
+        (name, Image::from_file_with_format(&bytes, Some(ImageFormat::Png)))
+    }).collect();
+
+    //shelves, tallest first so each row wastes as little as it can
+    sprites.sort_by(|a, b| b.1.height.cmp(&a.1.height).then(a.0.cmp(&b.0)));
+
+    let mut placed = Vec::new();
+    let (mut x, mut y, mut shelf) = (0, 0, 0);
+    for (name, image) in sprites.iter() {
+        let (width, height) = (image.width as usize, image.height as usize);
+        assert!(width <= WIDTH, "{} is wider than the atlas", name);
+
+        if x+width > WIDTH {
+            x = 0;
+            y += shelf+PADDING;
+            shelf = 0;
+        }
+        placed.push((name, image, x, y));
+        x += width+PADDING;
+        shelf = shelf.max(height);
+    }
+
+    let mut atlas = Image::gen_image_color(WIDTH as u16, (y+shelf) as u16, Color::new(0.0, 0.0, 0.0, 0.0));
+    let mut index = String::from("# written by pack_atlas, name = [x, y, width, height]\n\n[regions]\n");
+
+    for (name, image, left, top) in placed.iter() {
+        let (width, height) = (image.width as usize, image.height as usize);
+        let source = image.get_image_data();
+        let target = atlas.get_image_data_mut();
+        for row in 0..height {
+            for column in 0..width {
+                target[(top+row)*WIDTH+left+column] = source[row*width+column];
+            }
+        }
+
+        index.push_str(&format!("{} = [{}, {}, {}, {}]\n", name, left, top, width, height));
+    }
+
+    atlas.export_png(&format!("{}.png", output));
+    fs::write(format!("{}.toml", output), index).unwrap_or_else(|error| panic!("couldn't write the index: {}", error));
+
+    println!("packed {} sprites into {}.png", placed.len(), output);
+}
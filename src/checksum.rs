@@ -0,0 +1,156 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const DESYNC_DIRECTORY: &str = "skill-duel/desyncs";
+
+//fnv-1a, the same on every platform and build unlike the standard library's hasher
+const OFFSET: u64 = 0xCBF2_9CE4_8422_2325;
+const PRIME: u64 = 0x0000_0100_0000_01B3;
+
+//hashes gameplay state one field at a time in a fixed order. floats go in as their bits, so a difference
+//in the last place still counts. recording keeps every field by name for writing out a desync report
+pub struct StateHash {
+    hash: u64,
+    fields: Option<Vec<(String, u64)>>,
+}
+
+impl StateHash {
+    pub fn new(record: bool) -> Self {
+        Self {
+            hash: OFFSET,
+            fields: if record {Some(Vec::new())} else {None},
+        }
+    }
+
+    //the name is only formatted when recording, hashing every tick doesn't pay for it
+    pub fn field(&mut self, name: fmt::Arguments, value: u64) {
+        for byte in value.to_le_bytes().iter() {
+            self.hash = (self.hash^*byte as u64).wrapping_mul(PRIME);
+        }
+        if let Some(ref mut fields) = self.fields {
+            fields.push((name.to_string(), value));
+        }
+    }
+
+    pub fn float(&mut self, name: fmt::Arguments, value: f32) {
+        self.field(name, value.to_bits() as u64);
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.hash
+    }
+
+    pub fn fields(self) -> Vec<(String, u64)> {
+        self.fields.unwrap_or_default()
+    }
+}
+
+//where reports go unless told otherwise, next to the settings
+pub fn report_directory() -> PathBuf {
+    match dirs::config_dir() {
+        Some(directory) => directory.join(DESYNC_DIRECTORY),
+        None => PathBuf::from("desyncs"),
+    }
+}
+
+//writes both sides of a desync into the directory, the differing fields first and then everything.
+//both are recorded fields of the same tick, player is whose side this is. returns where it went
+pub fn report(directory: &Path, tick: u32, player: u32, local: &[(String, u64)], remote: &[(String, u64)]) -> io::Result<PathBuf> {
+    fs::create_dir_all(directory)?;
+
+    let mut text = format!("desync at tick {}\n\n[different]\n", tick);
+    text.push_str(&diff(local, remote));
+    for (heading, fields) in [("local", local), ("remote", remote)].iter() {
+        text.push_str(&format!("\n[{}]\n", heading));
+        for (name, value) in fields.iter() {
+            text.push_str(&format!("{} = {}\n", name, value));
+        }
+    }
+
+    let path = directory.join(format!("tick-{}-player-{}.txt", tick, player));
+    fs::write(&path, text)?;
+    Ok(path)
+}
+
+//one line per field that doesn't match, including any only one side has
+fn diff(local: &[(String, u64)], remote: &[(String, u64)]) -> String {
+    let mut text = String::new();
+    for (name, value) in local.iter() {
+        match remote.iter().find(|(other, _)| other == name) {
+            Some((_, other)) if other == value => {},
+            Some((_, other)) => text.push_str(&format!("{}: {} != {}\n", name, value, other)),
+            None => text.push_str(&format!("{}: {} != missing\n", name, value)),
+        }
+    }
+    for (name, value) in remote.iter() {
+        if !local.iter().any(|(other, _)| other == name) {
+            text.push_str(&format!("{}: missing != {}\n", name, value));
+        }
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use macroquad::prelude::*;
+
+    use super::*;
+    use crate::input::Input;
+    use crate::{Class, Game, WeaponType};
+
+    //the same few seconds of play every time: both players strafe, fire, switch weapons and dash
+    fn script(tick: u32, player: u32) -> Input {
+        let turn = tick as f32/40.0+player as f32;
+        Input {
+            movement: Vec2::new(turn.cos(), turn.sin()),
+            fire: tick%7 < 3,
+            fire_pressed: tick.is_multiple_of(7),
+            alt_fire: tick%50 > 40,
+            alt_fire_pressed: tick%50 == 41,
+            dash: tick%90 == 45,
+            weapon: if tick.is_multiple_of(120) {Some(WeaponType::slot((tick/120) as usize%6+1))} else {None},
+            ..Input::idle(turn*2.0)
+        }
+    }
+
+    fn play(seed: u64, ticks: u32) -> (Game, Vec<u64>) {
+        let mut game = Game::new(seed);
+        let mut checksums = Vec::new();
+        for tick in 0..ticks {
+            let inputs: Vec<(u32, Input)> = (0..2).map(|player| (player, script(tick, player))).collect();
+            game.step(&inputs);
+            checksums.push(game.checksum);
+        }
+
+        (game, checksums)
+    }
+
+    #[test]
+    fn same_inputs_same_checksums() {
+        let (_, first) = play(7, 600);
+        let (_, second) = play(7, 600);
+        assert_eq!(first, second);
+
+        //and it isn't just hashing nothing
+        let (_, other) = play(8, 600);
+        assert_ne!(first, other);
+    }
+
+    #[test]
+    fn diff_names_the_field() {
+        let (mut game, _) = play(7, 60);
+        let before = game.state(true).fields();
+        if let Class::Player { ref mut health, .. } = game.entities[0].class {
+            *health -= 1.0;
+        }
+        let after = game.state(true);
+        assert_ne!(after.finish(), game.checksum);
+
+        let difference = diff(&before, &after.fields());
+        assert_eq!(difference.lines().count(), 1);
+        assert!(difference.starts_with("entities[0].player.health: "));
+    }
+}
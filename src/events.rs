@@ -0,0 +1,168 @@
+use macroquad::prelude::Vec2;
+
+use crate::{PickupKind, WeaponType};
+
+//what happened during a tick, in the order the simulation ran into it. drawing, sound and anything
+//keeping score work off these instead of reaching into the simulation, so a headless run produces the same list.
+//players are named by id since entity indices move around as things are removed
+#[derive(Clone, Copy)]
+pub enum GameEvent {
+    ShotFired { player: u32, weapon: WeaponType, alt: bool, position: Vec2, direction: f32, velocity: Vec2 },
+    //source is where the damage came from, the attacker if they're still around
+    Hit { target: u32, attacker: Option<u32>, weapon: WeaponType, damage: f32, position: Vec2, source: Vec2 },
+    Kill { victim: u32, killer: Option<u32>, position: Vec2 },
+    //a player opened or closed their pause menu
+    Paused { player: u32, paused: bool },
+    GoldCollected { player: u32, total: u32 },
+    PickupTaken { player: u32, kind: PickupKind, position: Vec2 },
+    Explosion { position: Vec2 },
+    //a projectile stopped against a wall
+    WallHit { position: Vec2 },
+    WeaponSwitched { player: u32 },
+}
+
+#[cfg(test)]
+mod tests {
+    use macroquad::prelude::*;
+
+    use super::*;
+    use crate::input::Input;
+    use crate::{Class, Entity, Game, PickupKind, Weapon, MAP_SIZE_X};
+
+    //two players on open ground and nothing else, player 0 holding the given weapon and player 1 on low health
+    fn duel(weapon: WeaponType) -> Game {
+        let mut game = Game::new(3);
+        game.entities.truncate(2);
+        for (index, x) in [600.0, 900.0].iter().enumerate() {
+            game.entities[index].position = Vec2::new(*x, 1000.0);
+            if let Class::Player { weapon: ref mut held, ref mut health, .. } = game.entities[index].class {
+                *held = Weapon::new(weapon);
+                held.last_fire = 1000;
+                held.scoped = 1000;
+                *health = if index == 1 {20.0} else {100.0};
+            }
+        }
+        game
+    }
+
+    //player 0 plays the given input and player 1 stands still, everything that happened in order
+    fn run(game: &mut Game, ticks: u32, input: impl Fn(u32) -> Input) -> Vec<GameEvent> {
+        (0..ticks).flat_map(|tick| game.step(&[(0, input(tick))])).collect()
+    }
+
+    #[test]
+    fn shot_hit_kill() {
+        let mut game = duel(WeaponType::Sniper);
+        let events = run(&mut game, 30, |tick| Input { fire_pressed: tick == 0, alt_fire: true, ..Input::idle(0.0) });
+
+        let shot = events.iter().position(|event| matches!(event, GameEvent::ShotFired { player: 0, weapon: WeaponType::Sniper, alt: false, .. }));
+        let hit = events.iter().position(|event| matches!(event, GameEvent::Hit { target: 1, attacker: Some(0), weapon: WeaponType::Sniper, .. }));
+        let kill = events.iter().position(|event| matches!(event, GameEvent::Kill { victim: 1, killer: Some(0), .. }));
+        assert!(shot.is_some() && shot < hit && hit < kill, "shot {:?} hit {:?} kill {:?}", shot, hit, kill);
+
+        //one of each, nobody else was involved
+        assert_eq!(events.iter().filter(|event| matches!(event, GameEvent::ShotFired { .. })).count(), 1);
+        assert_eq!(events.iter().filter(|event| matches!(event, GameEvent::Hit { .. })).count(), 1);
+        assert_eq!(events.iter().filter(|event| matches!(event, GameEvent::Kill { .. })).count(), 1);
+        assert!(game.over);
+    }
+
+    #[test]
+    fn shot_credited_after_an_entity_before_the_shooter_goes() {
+        let mut game = duel(WeaponType::Sniper);
+        //a spent shot ahead of both players, gone on the first tick, moves them both down one index
+        game.entities.insert(0, Entity {
+            position: Vec2::new(100.0, 100.0),
+            velocity: Vec2::new(0.0, 0.0),
+            class: Class::Projectile(WeaponType::Gunner, 0, 1, false),
+        });
+        let events = run(&mut game, 30, |tick| Input { fire_pressed: tick == 0, alt_fire: true, ..Input::idle(0.0) });
+
+        let hit = events.iter().position(|event| matches!(event, GameEvent::Hit { target: 1, attacker: Some(0), weapon: WeaponType::Sniper, .. }));
+        let kill = events.iter().position(|event| matches!(event, GameEvent::Kill { victim: 1, killer: Some(0), .. }));
+        assert!(hit.is_some() && hit < kill, "hit {:?} kill {:?}", hit, kill);
+    }
+
+    #[test]
+    fn knife_doesnt_cut_through_walls() {
+        for wall in [false, true].iter() {
+            let mut game = duel(WeaponType::Knife(true));
+            game.entities[1].position = Vec2::new(725.0, 1000.0);
+            game.map[13*MAP_SIZE_X+20].1 = *wall;
+            let events = run(&mut game, 5, |tick| Input { fire_pressed: tick == 0, ..Input::idle(0.0) });
+            assert_eq!(events.iter().any(|event| matches!(event, GameEvent::Hit { target: 1, .. })), !*wall);
+        }
+    }
+
+    #[test]
+    fn vent_doesnt_go_through_walls() {
+        for wall in [false, true].iter() {
+            let mut game = duel(WeaponType::Sprayer);
+            game.entities[1].position = Vec2::new(725.0, 1000.0);
+            game.map[13*MAP_SIZE_X+20].1 = *wall;
+            if let Class::Player { ref mut weapon, .. } = game.entities[0].class {
+                weapon.heat = 50;
+                weapon.last_alt_fire = 1000;
+            }
+            let events = run(&mut game, 5, |tick| Input { alt_fire_pressed: tick == 0, ..Input::idle(0.0) });
+            assert_eq!(events.iter().any(|event| matches!(event, GameEvent::Hit { target: 1, .. })), !*wall);
+        }
+    }
+
+    #[test]
+    fn grenade_kill_goes_to_the_thrower() {
+        let mut game = duel(WeaponType::Knife(true));
+        game.entities.push(Entity {
+            position: Vec2::new(930.0, 1000.0),
+            velocity: Vec2::new(0.0, 0.0),
+            class: Class::Grenade { fuse: 0, height: 0.0, lift: 0.0, owner: 0 },
+        });
+        let events = run(&mut game, 5, |_| Input::idle(0.0));
+
+        let hit = events.iter().position(|event| matches!(event, GameEvent::Hit { target: 1, attacker: Some(0), weapon: WeaponType::Grenade, .. }));
+        let kill = events.iter().position(|event| matches!(event, GameEvent::Kill { victim: 1, killer: Some(0), .. }));
+        assert!(hit.is_some() && hit < kill, "hit {:?} kill {:?}", hit, kill);
+    }
+
+    #[test]
+    fn pickup_once_claimed() {
+        let mut game = duel(WeaponType::Knife(true));
+        game.entities.push(Entity {
+            position: Vec2::new(600.0, 1000.0),
+            velocity: Vec2::new(0.0, 0.0),
+            class: Class::Pickup { kind: PickupKind::Health, respawn: 0, claimant: None, claim: 0 },
+        });
+        let claim = game.tuning.pickup_claim;
+
+        //standing on it a tick short of the claim gets nothing
+        let events = run(&mut game, claim-1, |_| Input::idle(0.0));
+        assert!(!events.iter().any(|event| matches!(event, GameEvent::PickupTaken { .. })));
+
+        //then exactly one on the very next tick, and it doesn't come straight back
+        let events = run(&mut game, claim*2, |_| Input::idle(0.0));
+        let taken: Vec<&GameEvent> = events.iter().filter(|event| matches!(event, GameEvent::PickupTaken { .. })).collect();
+        assert_eq!(taken.len(), 1);
+        assert!(matches!(taken[0], GameEvent::PickupTaken { player: 0, kind: PickupKind::Health, .. }));
+        assert!(matches!(events[0], GameEvent::PickupTaken { .. }));
+    }
+
+    #[test]
+    fn claim_survives_an_entity_before_the_claimant_going() {
+        let mut game = duel(WeaponType::Knife(true));
+        game.entities.push(Entity {
+            position: Vec2::new(600.0, 1000.0),
+            velocity: Vec2::new(0.0, 0.0),
+            class: Class::Pickup { kind: PickupKind::Health, respawn: 0, claimant: None, claim: 0 },
+        });
+        let claim = game.tuning.pickup_claim;
+
+        let mut events = run(&mut game, claim/2, |_| Input::idle(0.0));
+        game.entities.insert(0, Entity {
+            position: Vec2::new(100.0, 100.0),
+            velocity: Vec2::new(0.0, 0.0),
+            class: Class::Projectile(WeaponType::Gunner, 0, 1, false),
+        });
+        events.extend(run(&mut game, claim-claim/2, |_| Input::idle(0.0)));
+        assert!(events.iter().any(|event| matches!(event, GameEvent::PickupTaken { player: 0, .. })));
+    }
+}
@@ -0,0 +1,180 @@
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+//trig for the simulation that comes out bit for bit the same on every machine. the standard library hands sin,
+//cos and atan to the platform's maths library, which differs between compilers and cpus, and a lockstep match or
+//a replay falls apart the first time two machines round one differently. these only use +, -, *, / and sqrt,
+//which ieee 754 pins down exactly, worked in f64 and rounded once at the end.
+//rendering doesn't need any of this and keeps using the normal functions.
+//it's a trait on f32 rather than a number type of its own because trig is the only part that needs replacing,
+//everything else already rounds the same everywhere and the simulation's Vec2s can stay as they are
+pub trait Exact {
+    fn exact_sin(self) -> Self;
+    fn exact_cos(self) -> Self;
+    fn exact_atan(self) -> Self;
+    fn exact_atan2(self, x: Self) -> Self;
+}
+
+impl Exact for f32 {
+    fn exact_sin(self) -> f32 {
+        let (sin, _) = sin_cos(self as f64);
+        sin as f32
+    }
+
+    fn exact_cos(self) -> f32 {
+        let (_, cos) = sin_cos(self as f64);
+        cos as f32
+    }
+
+    fn exact_atan(self) -> f32 {
+        atan(self as f64) as f32
+    }
+
+    fn exact_atan2(self, x: f32) -> f32 {
+        atan2(self as f64, x as f64) as f32
+    }
+}
+
+//taylor series on the quarter turn around zero, past x^13 the terms are too small for an f32 to notice
+fn sin_cos_reduced(x: f64) -> (f64, f64) {
+    let square = x*x;
+    let mut sin = x;
+    let mut cos = 1.0;
+    let mut sin_term = x;
+    let mut cos_term = 1.0;
+    for n in 1..8 {
+        let n = n as f64;
+        sin_term *= -square/((2.0*n)*(2.0*n+1.0));
+        cos_term *= -square/((2.0*n-1.0)*(2.0*n));
+        sin += sin_term;
+        cos += cos_term;
+    }
+
+    (sin, cos)
+}
+
+fn sin_cos(x: f64) -> (f64, f64) {
+    if !x.is_finite() {
+        return (f64::NAN, f64::NAN);
+    }
+
+    //down to the nearest multiple of a quarter turn and what's left over, then swap and flip by which quarter it was
+    let quarter = (x/FRAC_PI_2+0.5).floor();
+    let (sin, cos) = sin_cos_reduced(x-quarter*FRAC_PI_2);
+    match (quarter as i64).rem_euclid(4) {
+        0 => (sin, cos),
+        1 => (cos, -sin),
+        2 => (-sin, -cos),
+        _ => (-cos, sin),
+    }
+}
+
+fn atan(x: f64) -> f64 {
+    if x.is_nan() {
+        return f64::NAN;
+    }
+    if x < 0.0 {
+        return -atan(-x);
+    }
+    //past 1 it's a quarter turn less the reciprocal's
+    if x > 1.0 {
+        return FRAC_PI_2-atan(1.0/x);
+    }
+
+    //past tan(pi/8) shift it down around pi/4 so the series only ever sees small numbers
+    let (offset, x) = if x > 0.414_213_562_373_095_1 {(FRAC_PI_4, (x-1.0)/(x+1.0))} else {(0.0, x)};
+    let square = x*x;
+    let mut term = x;
+    let mut sum = x;
+    for n in 1..12 {
+        term *= -square;
+        sum += term/(2*n+1) as f64;
+    }
+
+    offset+sum
+}
+
+//same quadrants as f32::atan2
+fn atan2(y: f64, x: f64) -> f64 {
+    if x > 0.0 {
+        atan(y/x)
+    } else if x < 0.0 {
+        if y < 0.0 {atan(y/x)-PI} else {atan(y/x)+PI}
+    } else if y > 0.0 {
+        FRAC_PI_2
+    } else if y < 0.0 {
+        -FRAC_PI_2
+    } else {
+        0.0
+    }
+}
+
+//golden values, the exact bits every machine has to produce. if one of these changes on some platform the
+//simulation isn't deterministic there, if they change everywhere the maths was changed on purpose and they need redoing
+#[cfg(test)]
+mod tests {
+    use macroquad::prelude::*;
+
+    use super::*;
+    use crate::{apply_recoil, check_hit, ray_end, MAP_SIZE, MAP_SIZE_X};
+
+    #[test]
+    fn trig() {
+        let golden: [(f32, u32, u32, u32); 8] = [
+            (0.0, 0x00000000, 0x3f800000, 0x00000000),
+            (0.5, 0x3ef57744, 0x3f60a940, 0x3eed6338),
+            (1.0, 0x3f576aa4, 0x3f0a5140, 0x3f490fdb),
+            (-2.0, 0xbf68c7b7, 0xbed51133, 0xbf8db70d),
+            (PI as f32, 0xb3bbbd2e, 0xbf800000, 0x3fa19dc5),
+            (10.0, 0xbf0b44f8, 0xbf56cd64, 0x3fbc4de9),
+            (-100.25, 0x3e8df805, 0x3f75f639, 0xbfc7c901),
+            (1000.0, 0x3f53ae61, 0x3f0ff813, 0x3fc8ef16),
+        ];
+        for (x, sin, cos, atan) in golden.iter() {
+            assert_eq!(x.exact_sin().to_bits(), *sin, "sin {}", x);
+            assert_eq!(x.exact_cos().to_bits(), *cos, "cos {}", x);
+            assert_eq!(x.exact_atan().to_bits(), *atan, "atan {}", x);
+        }
+
+        let golden: [(f32, f32, u32); 6] = [
+            (1.0, 1.0, 0x3f490fdb),
+            (1.0, -1.0, 0x4016cbe4),
+            (-1.0, -1.0, 0xc016cbe4),
+            (-3.0, 0.5, 0xbfb3ec43),
+            (0.0, -2.0, 0x40490fdb),
+            (2.0, 0.0, 0x3fc90fdb),
+        ];
+        for (y, x, atan2) in golden.iter() {
+            assert_eq!(y.exact_atan2(*x).to_bits(), *atan2, "atan2 {} {}", y, x);
+        }
+    }
+
+    //not bit for bit with the platform's own, but close enough nobody could tell in play
+    #[test]
+    fn matches_std() {
+        for step in -2000..2000 {
+            let x = step as f32*0.01;
+            assert!((x.exact_sin()-x.sin()).abs() < 1e-6, "sin {}", x);
+            assert!((x.exact_cos()-x.cos()).abs() < 1e-6, "cos {}", x);
+            assert!((x.exact_atan()-x.atan()).abs() < 1e-6, "atan {}", x);
+            assert!((x.exact_atan2(1.5-x)-x.atan2(1.5-x)).abs() < 1e-6, "atan2 {}", x);
+        }
+    }
+
+    #[test]
+    fn collision() {
+        assert!(check_hit(Vec2::new(0.0, 0.0), Vec2::new(100.0, 0.0), 10.0, Vec2::new(50.0, 9.0)));
+        assert!(!check_hit(Vec2::new(0.0, 0.0), Vec2::new(100.0, 0.0), 10.0, Vec2::new(50.0, 11.0)));
+        assert!(!check_hit(Vec2::new(0.0, 0.0), Vec2::new(100.0, 100.0), 5.0, Vec2::new(110.0, 110.0)));
+
+        let mut map = [(0.0, false); MAP_SIZE];
+        for y in 0..100 {
+            map[50*MAP_SIZE_X+y].1 = true;
+        }
+        let end = ray_end(&map, Vec2::new(2000.0, 1234.5), 0.3, 2000.0);
+        assert_eq!((end.x.to_bits(), end.y.to_bits()), (0x451adaa9, 0x44acc85c));
+
+        let mut velocity = Vec2::new(3.0, -4.0);
+        apply_recoil(&mut velocity, 2.5, 6.0, 20.0);
+        assert_eq!((velocity.x.to_bits(), velocity.y.to_bits()), (0x40f9d1cf, 0xc0f2e81a));
+    }
+}
@@ -0,0 +1,174 @@
+use std::f32::consts::PI;
+
+use macroquad::prelude::*;
+
+use crate::events::GameEvent;
+use crate::hud::Layout;
+use crate::rng::Stream;
+use crate::settings::Settings;
+
+const HIT_MARKER_TICKS: u16 = 12;
+const NUMBER_TICKS: u16 = 50;
+//hits on the same target this close together add onto one number, so a shotgun blast reads as one hit
+const NUMBER_STACK_TICKS: u16 = 10;
+const INDICATOR_TICKS: u16 = 60;
+const INDICATOR_RADIUS: f32 = 160.0;
+//explosions further than this don't shake the camera
+const SHAKE_RANGE: f32 = 1000.0;
+const SHAKE_DECAY: f32 = 0.03;
+const SHAKE_STRENGTH: f32 = 25.0;
+
+struct DamageNumber {
+    target: u32,
+    position: Vec2,
+    damage: f32,
+    age: u16,
+}
+
+//everything that tells the local player they hit or got hit. lives on the renderer's side,
+//built from events, so none of it changes the match
+pub struct Feedback {
+    hit_markers: bool,
+    damage_numbers: bool,
+    damage_indicators: bool,
+    screen_shake: bool,
+
+    //ticks left on the crosshair marker and whether it was a kill
+    hit_marker: u16,
+    killed: bool,
+    numbers: Vec<DamageNumber>,
+    //angle toward whoever hit us and how long ago
+    indicators: Vec<(f32, u16)>,
+    //0 to 1, the shake is this squared so small knocks barely register
+    trauma: f32,
+}
+
+impl Feedback {
+    pub fn new(settings: &Settings) -> Self {
+        Self {
+            hit_markers: settings.gameplay.hit_markers,
+            damage_numbers: settings.gameplay.damage_numbers,
+            damage_indicators: settings.gameplay.damage_indicators,
+            screen_shake: settings.gameplay.screen_shake,
+
+            hit_marker: 0,
+            killed: false,
+            numbers: Vec::new(),
+            indicators: Vec::new(),
+            trauma: 0.0,
+        }
+    }
+
+    pub fn apply_settings(&mut self, settings: &Settings) {
+        self.hit_markers = settings.gameplay.hit_markers;
+        self.damage_numbers = settings.gameplay.damage_numbers;
+        self.damage_indicators = settings.gameplay.damage_indicators;
+        self.screen_shake = settings.gameplay.screen_shake;
+    }
+
+    //local is who we're playing as and where they stand
+    pub fn events(&mut self, events: &[GameEvent], local: u32, position: Vec2) {
+        for event in events.iter() {
+            match *event {
+                GameEvent::Hit { target, attacker, damage, position: hit, source, .. } => {
+                    if attacker == Some(local) {
+                        self.hit_marker = HIT_MARKER_TICKS;
+                        self.killed = false;
+
+                        match self.numbers.iter_mut().find(|number| number.target == target && number.age < NUMBER_STACK_TICKS) {
+                            Some(number) => {
+                                number.damage += damage;
+                                number.age = 0;
+                            },
+                            None => self.numbers.push(DamageNumber { target, position: hit, damage, age: 0 }),
+                        }
+                    }
+
+                    if target == local {
+                        let offset = source-position;
+                        self.indicators.push((offset.y.atan2(offset.x), 0));
+                        self.trauma += damage/40.0;
+                    }
+                },
+
+                GameEvent::Kill { killer, .. } if killer == Some(local) => {
+                    self.hit_marker = HIT_MARKER_TICKS*2;
+                    self.killed = true;
+                },
+
+                GameEvent::Explosion { position: explosion } => {
+                    self.trauma += (1.0-explosion.distance(position)/SHAKE_RANGE).max(0.0)*0.6;
+                },
+
+                _ => {},
+            }
+        }
+
+        self.trauma = self.trauma.min(1.0);
+    }
+
+    //how far to knock the camera this frame
+    pub fn shake(&self, rng: &mut Stream) -> Vec2 {
+        if !self.screen_shake {
+            return Vec2::new(0.0, 0.0);
+        }
+
+        let angle = rng.gen_range(0.0, 2.0*PI);
+        Vec2::new(angle.cos(), angle.sin())*self.trauma*self.trauma*SHAKE_STRENGTH
+    }
+
+    //damage numbers float up off whoever took the hit, drawn with the world camera
+    pub fn draw_world(&self) {
+        if !self.damage_numbers {
+            return;
+        }
+
+        for number in self.numbers.iter() {
+            let fade = 1.0-number.age as f32/NUMBER_TICKS as f32;
+            let text = format!("{}", number.damage.round() as i32);
+            let size = 30.0+number.damage.min(50.0)*0.6;
+            let text_center = get_text_center(&text, None, size as u16, 1.0, 0.0);
+            draw_text(&text, number.position.x-text_center.x, number.position.y-70.0-number.age as f32*1.5-text_center.y, size, Color::new(1.0, 0.9, 0.2, fade));
+        }
+    }
+
+    //hit markers sit on the cursor and damage indicators ring the middle of the screen, drawn on the screen space hud
+    pub fn draw_hud(&self, layout: &Layout) {
+        let center = layout.center();
+        let cursor = Vec2::from(mouse_position());
+
+        if self.hit_markers && self.hit_marker > 0 {
+            let color = if self.killed {RED} else {WHITE};
+            let fade = Color::new(color.r, color.g, color.b, self.hit_marker as f32/HIT_MARKER_TICKS as f32);
+            for corner in [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)].iter() {
+                draw_line(cursor.x+corner.0*layout.size(8.0), cursor.y+corner.1*layout.size(8.0), cursor.x+corner.0*layout.size(18.0), cursor.y+corner.1*layout.size(18.0), layout.size(3.0), fade);
+            }
+        }
+
+        if self.damage_indicators {
+            let radius = layout.size(INDICATOR_RADIUS);
+            for (angle, age) in self.indicators.iter() {
+                let fade = 1.0-*age as f32/INDICATOR_TICKS as f32;
+                let tip = center+Vec2::new(angle.cos(), angle.sin())*(radius+layout.size(20.0));
+                let left = center+Vec2::new((angle-0.15).cos(), (angle-0.15).sin())*radius;
+                let right = center+Vec2::new((angle+0.15).cos(), (angle+0.15).sin())*radius;
+                draw_triangle(tip, left, right, Color::new(1.0, 0.0, 0.0, 0.7*fade));
+            }
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.hit_marker = self.hit_marker.saturating_sub(1);
+        self.trauma = (self.trauma-SHAKE_DECAY).max(0.0);
+
+        for number in self.numbers.iter_mut() {
+            number.age += 1;
+        }
+        self.numbers.retain(|number| number.age < NUMBER_TICKS);
+
+        for (_, age) in self.indicators.iter_mut() {
+            *age += 1;
+        }
+        self.indicators.retain(|(_, age)| *age < INDICATOR_TICKS);
+    }
+}
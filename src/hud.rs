@@ -0,0 +1,173 @@
+use macroquad::prelude::*;
+
+use crate::events::GameEvent;
+use crate::settings::Palette;
+use crate::WeaponType;
+
+//everything on the hud is laid out on a screen this big and scaled to fit the window
+const REFERENCE_WIDTH: f32 = 1600.0;
+const REFERENCE_HEIGHT: f32 = 900.0;
+
+const KILL_FEED_TICKS: u16 = 300;
+const KILL_FEED_LENGTH: usize = 5;
+
+#[derive(Clone, Copy)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Center,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    //how far across and down the screen the anchor sits
+    fn factors(&self) -> (f32, f32) {
+        match self {
+            Anchor::TopLeft => (0.0, 0.0),
+            Anchor::Top => (0.5, 0.0),
+            Anchor::TopRight => (1.0, 0.0),
+            Anchor::Center => (0.5, 0.5),
+            Anchor::Bottom => (0.5, 1.0),
+            Anchor::BottomRight => (1.0, 1.0),
+        }
+    }
+}
+
+//the screen space ui layer. the window is measured in physical pixels, so high dpi screens scale up with it.
+//drawing and the mouse both go through here so they can't disagree about where something is
+#[derive(Clone, Copy)]
+pub struct Layout {
+    width: f32,
+    height: f32,
+    scale: f32,
+}
+
+impl Layout {
+    pub fn current() -> Self {
+        let (width, height) = (screen_width(), screen_height());
+        Self {
+            width,
+            height,
+            scale: (width/REFERENCE_WIDTH).min(height/REFERENCE_HEIGHT),
+        }
+    }
+
+    //units on the reference screen to pixels
+    pub fn size(&self, units: f32) -> f32 {
+        units*self.scale
+    }
+
+    pub fn center(&self) -> Vec2 {
+        Vec2::new(self.width/2.0, self.height/2.0)
+    }
+
+    //a box of size units, offset units in from its anchor. offsets point into the screen from edges and
+    //right and down from the middle
+    pub fn place(&self, anchor: Anchor, offset: Vec2, size: Vec2) -> Rect {
+        let (across, down) = anchor.factors();
+        let size = size*self.scale;
+        let offset = offset*self.scale;
+
+        Rect::new(
+            across*(self.width-size.x)+if across == 1.0 {-offset.x} else {offset.x},
+            down*(self.height-size.y)+if down == 1.0 {-offset.y} else {offset.y},
+            size.x,
+            size.y,
+        )
+    }
+
+    //text placed like a box, returns where it went
+    pub fn text(&self, text: &str, anchor: Anchor, offset: Vec2, font_size: f32, color: Color) -> Rect {
+        let measured = measure_text(text, None, self.size(font_size) as u16, 1.0);
+        let area = self.place(anchor, offset, Vec2::new(measured.width, measured.height)/self.scale);
+        draw_text(text, area.x, area.y+measured.offset_y, self.size(font_size), color);
+        area
+    }
+
+    //six slots along the bottom, index from 1 like WeaponType::slot
+    pub fn weapon_slot(&self, index: usize) -> Rect {
+        self.place(Anchor::Bottom, Vec2::new((index as f32-3.5)*100.0, 10.0), Vec2::new(80.0, 80.0))
+    }
+
+    pub fn slot_at(&self, position: Vec2) -> Option<usize> {
+        (1..7).find(|index| self.weapon_slot(*index).contains(position))
+    }
+}
+
+pub fn player_name(id: u32, local: u32) -> String {
+    if id == local {"You".to_string()} else {format!("Player {}", id)}
+}
+
+//who killed who, newest at the top
+pub struct KillFeed {
+    entries: Vec<(Option<u32>, u32, Option<WeaponType>, u16)>,
+    //what each player was last hit by, the kill goes to whoever landed that
+    last_hit: Vec<(u32, WeaponType)>,
+}
+
+impl KillFeed {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            last_hit: Vec::new(),
+        }
+    }
+
+    pub fn events(&mut self, events: &[GameEvent]) {
+        for event in events.iter() {
+            match *event {
+                GameEvent::Hit { target, attacker: Some(_), weapon, .. } => {
+                    self.last_hit.retain(|(id, _)| *id != target);
+                    self.last_hit.push((target, weapon));
+                },
+                GameEvent::Kill { victim, killer, .. } => {
+                    let weapon = self.last_hit.iter().find(|(id, _)| *id == victim).map(|(_, weapon)| *weapon);
+                    self.last_hit.retain(|(id, _)| *id != victim);
+                    self.entries.insert(0, (killer, victim, weapon.filter(|_| killer.is_some()), 0));
+                    self.entries.truncate(KILL_FEED_LENGTH);
+                },
+                _ => {},
+            }
+        }
+    }
+
+    pub fn tick(&mut self) {
+        for (_, _, _, age) in self.entries.iter_mut() {
+            *age += 1;
+        }
+        self.entries.retain(|(_, _, _, age)| *age < KILL_FEED_TICKS);
+    }
+
+    //top is how far down the right edge to start
+    pub fn draw(&self, layout: &Layout, top: f32, local: u32, palette: Palette) {
+        let color = |id: u32| if id == local {palette.ally()} else {palette.enemy()};
+
+        for (row, (killer, victim, weapon, age)) in self.entries.iter().enumerate() {
+            let fade = (1.0-*age as f32/KILL_FEED_TICKS as f32).min(0.5)*2.0;
+            let top = top+row as f32*36.0;
+
+            //laid out from the right edge leftward, so the parts go in backwards
+            let mut right = 20.0;
+            let mut part = |text: &str, color: Color| {
+                let area = layout.text(text, Anchor::TopRight, Vec2::new(right, top), 30.0, Color { a: fade, ..color });
+                right += area.w/layout.scale;
+            };
+            match killer {
+                Some(killer) => {
+                    part(&player_name(*victim, local), color(*victim));
+                    match weapon {
+                        Some(weapon) => part(&format!(" [{}] ", weapon.name()), WHITE),
+                        None => part(" killed ", WHITE),
+                    }
+                    part(&player_name(*killer, local), color(*killer));
+                },
+                None => {
+                    part(" died", WHITE);
+                    part(&player_name(*victim, local), color(*victim));
+                },
+            }
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use macroquad::prelude::*;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::hud::Layout;
+use crate::{WeaponType, ROOT_2};
+
+//everything that can be bound to an action. escape and backspace are left out, they always back out of menus
+const KEYS: [KeyCode; 56] = [
+    KeyCode::A, KeyCode::B, KeyCode::C, KeyCode::D, KeyCode::E, KeyCode::F, KeyCode::G, KeyCode::H, KeyCode::I,
+    KeyCode::J, KeyCode::K, KeyCode::L, KeyCode::M, KeyCode::N, KeyCode::O, KeyCode::P, KeyCode::Q, KeyCode::R,
+    KeyCode::S, KeyCode::T, KeyCode::U, KeyCode::V, KeyCode::W, KeyCode::X, KeyCode::Y, KeyCode::Z,
+    KeyCode::Key0, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9,
+    KeyCode::Up, KeyCode::Down, KeyCode::Left, KeyCode::Right,
+    KeyCode::Space, KeyCode::Tab, KeyCode::Enter, KeyCode::CapsLock,
+    KeyCode::LeftShift, KeyCode::RightShift, KeyCode::LeftControl, KeyCode::RightControl, KeyCode::LeftAlt, KeyCode::RightAlt,
+    KeyCode::Comma, KeyCode::Period, KeyCode::Slash, KeyCode::Semicolon, KeyCode::Apostrophe, KeyCode::LeftBracket,
+    KeyCode::RightBracket, KeyCode::Minus, KeyCode::Equal, KeyCode::GraveAccent, KeyCode::Kp0, KeyCode::KpEnter,
+];
+
+//up to two keys for one action, saved by name
+#[derive(Clone, Copy, PartialEq)]
+pub struct Binding(pub [Option<KeyCode>; 2]);
+
+impl Binding {
+    fn new(first: KeyCode, second: Option<KeyCode>) -> Self {
+        Self([Some(first), second])
+    }
+
+    pub fn bindable(key: KeyCode) -> bool {
+        KEYS.contains(&key)
+    }
+
+    fn down(&self) -> bool {
+        self.0.iter().flatten().any(|key| is_key_down(*key))
+    }
+
+    fn pressed(&self) -> bool {
+        self.0.iter().flatten().any(|key| is_key_pressed(*key))
+    }
+
+    pub fn name(&self) -> String {
+        let names: Vec<String> = self.0.iter().flatten().map(|key| format!("{:?}", key)).collect();
+        if names.is_empty() {
+            "Unbound".to_string()
+        } else {
+            names.join(" / ")
+        }
+    }
+}
+
+impl Serialize for Binding {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let names: Vec<String> = self.0.iter().flatten().map(|key| format!("{:?}", key)).collect();
+        names.serialize(serializer)
+    }
+}
+
+//names that aren't bindable keys are dropped rather than failing the whole file
+impl<'de> Deserialize<'de> for Binding {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let names = Vec::<String>::deserialize(deserializer)?;
+        let mut keys = names.iter().filter_map(|name| KEYS.iter().find(|key| format!("{:?}", key) == *name).copied());
+
+        Ok(Self([keys.next(), keys.next()]))
+    }
+}
+
+//weapon slots stay on the number keys, they match the slots drawn on the hud
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Bindings {
+    pub up: Binding,
+    pub down: Binding,
+    pub left: Binding,
+    pub right: Binding,
+    pub parry: Binding,
+    pub dash: Binding,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Self {
+            up: Binding::new(KeyCode::W, Some(KeyCode::Up)),
+            down: Binding::new(KeyCode::S, Some(KeyCode::Down)),
+            left: Binding::new(KeyCode::A, Some(KeyCode::Left)),
+            right: Binding::new(KeyCode::D, Some(KeyCode::Right)),
+            parry: Binding::new(KeyCode::E, None),
+            dash: Binding::new(KeyCode::Space, Some(KeyCode::LeftShift)),
+        }
+    }
+}
+
+impl Bindings {
+    pub const NAMES: [&'static str; 6] = ["Move up", "Move down", "Move left", "Move right", "Parry", "Dash"];
+
+    pub fn get(&self, index: usize) -> Binding {
+        [self.up, self.down, self.left, self.right, self.parry, self.dash][index]
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> &mut Binding {
+        match index {
+            0 => &mut self.up,
+            1 => &mut self.down,
+            2 => &mut self.left,
+            3 => &mut self.right,
+            4 => &mut self.parry,
+            _ => &mut self.dash,
+        }
+    }
+
+    //a key only does one thing. whatever had it before swaps it for the old key of the one being rebound
+    pub fn rebind(&mut self, index: usize, key: KeyCode) {
+        let old = self.get(index).0[0];
+        for other in (0..Self::NAMES.len()).filter(|other| *other != index) {
+            let binding = self.get_mut(other);
+            let swap = if binding.0.contains(&old) {None} else {old};
+            for slot in binding.0.iter_mut().filter(|slot| **slot == Some(key)) {
+                *slot = swap;
+            }
+        }
+        *self.get_mut(index) = Binding([Some(key), None]);
+    }
+}
+
+//everything a player does in one tick. the local player reads it from the keyboard and mouse,
+//bots and replays hand the game the same thing so every player goes through one code path
+#[derive(Clone, Copy)]
+pub struct Input {
+    pub aim: f32,
+    pub movement: Vec2,
+    pub fire: bool,
+    pub fire_pressed: bool,
+    pub alt_fire: bool,
+    pub alt_fire_pressed: bool,
+    pub parry: bool,
+    pub dash: bool,
+    pub weapon: Option<WeaponType>,
+    pub paused: bool,
+}
+
+impl Input {
+    //stand still and keep facing the same way
+    pub fn idle(aim: f32) -> Self {
+        Self {
+            aim,
+            movement: Vec2::new(0.0, 0.0),
+            fire: false,
+            fire_pressed: false,
+            alt_fire: false,
+            alt_fire_pressed: false,
+            parry: false,
+            dash: false,
+            weapon: None,
+            paused: false,
+        }
+    }
+
+    //takes on the one frame presses of an input that was never sent, so they go with this one instead of being lost
+    pub fn carry(&mut self, unsent: &Input) {
+        self.fire_pressed |= unsent.fire_pressed;
+        self.alt_fire_pressed |= unsent.alt_fire_pressed;
+        self.parry |= unsent.parry;
+        self.dash |= unsent.dash;
+        self.weapon = self.weapon.or(unsent.weapon);
+    }
+
+    //how big an input is on the wire
+    pub const SIZE: usize = 14;
+
+    pub fn encode(&self, bytes: &mut Vec<u8>) {
+        for value in [self.aim, self.movement.x, self.movement.y].iter() {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        let flags = [self.fire, self.fire_pressed, self.alt_fire, self.alt_fire_pressed, self.parry, self.dash, self.paused];
+        bytes.push(flags.iter().enumerate().fold(0, |byte, (bit, flag)| byte | (*flag as u8) << bit));
+        //slots are 1 to 6, the other knife grip gets 7 and no switch is 0
+        bytes.push(match self.weapon {
+            None => 0,
+            Some(WeaponType::Knife(false)) => 7,
+            Some(weapon) => weapon.slot_index() as u8,
+        });
+    }
+
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < Self::SIZE {
+            return None;
+        }
+        let float = |at: usize| f32::from_le_bytes([bytes[at], bytes[at+1], bytes[at+2], bytes[at+3]]);
+        let flag = |bit: u8| bytes[12] & 1 << bit != 0;
+
+        Some(Self {
+            aim: float(0),
+            movement: Vec2::new(float(4), float(8)),
+            fire: flag(0),
+            fire_pressed: flag(1),
+            alt_fire: flag(2),
+            alt_fire_pressed: flag(3),
+            parry: flag(4),
+            dash: flag(5),
+            paused: flag(6),
+            weapon: match bytes[13] {
+                0 => None,
+                7 => Some(WeaponType::Knife(false)),
+                slot => Some(WeaponType::slot(slot as usize)),
+            },
+        })
+    }
+
+    pub fn read(camera: &Camera2D, position: Vec2, bindings: &Bindings) -> Self {
+        let mouse_diference = camera.screen_to_world(Vec2::from(mouse_position())) - position;
+
+        let mut input = Self::idle(mouse_diference.y.atan2(mouse_diference.x));
+
+        if bindings.up.down() {
+            input.movement.y -= 1.0;
+        }
+        if bindings.down.down() {
+            input.movement.y += 1.0;
+        }
+        if bindings.left.down() {
+            input.movement.x -= 1.0;
+        }
+        if bindings.right.down() {
+            input.movement.x += 1.0;
+        }
+
+        if input.movement.x.abs() + input.movement.y.abs() == 2.0 {
+            input.movement /= ROOT_2;
+        }
+
+        input.fire = is_mouse_button_down(MouseButton::Left);
+        input.fire_pressed = is_mouse_button_pressed(MouseButton::Left);
+        input.alt_fire = is_mouse_button_down(MouseButton::Right);
+        input.alt_fire_pressed = is_mouse_button_pressed(MouseButton::Right);
+        input.parry = bindings.parry.pressed();
+        input.dash = bindings.dash.pressed();
+
+        for (index, key) in [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4, KeyCode::Key5, KeyCode::Key6].iter().enumerate() {
+            if is_key_down(*key) {
+                input.weapon = Some(WeaponType::slot(index+1));
+                break;
+            }
+        }
+
+        //clicking a weapon slot switches to it instead of firing
+        if input.fire_pressed {
+            if let Some(index) = Layout::current().slot_at(Vec2::from(mouse_position())) {
+                input.weapon = Some(WeaponType::slot(index));
+                input.fire = false;
+                input.fire_pressed = false;
+            }
+        }
+
+        input
+    }
+}
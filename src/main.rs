@@ -1,96 +1,224 @@
-use std::borrow::{Borrow, BorrowMut};
-use std::f32::consts::PI;
-use std::process::exit;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::env;
+use std::f32::consts::{PI, SQRT_2};
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use macroquad::prelude::*;
 use macroquad::texture::DrawTextureParams;
 
+mod animation;
+mod assets;
+mod audio;
+mod checksum;
+mod events;
+mod exact;
+mod feedback;
+mod hud;
+mod input;
+mod map;
+mod minimap;
+mod net;
+mod rng;
+mod scenes;
+mod settings;
+mod snapshot;
+mod stats;
+mod tuning;
+mod visibility;
+
+use animation::{weapon_region, Animator, PlayerAnimation, Sprites};
+use assets::{Assets, TextureId, Textures};
+use audio::Audio;
+use checksum::StateHash;
+use events::GameEvent;
+use exact::Exact;
+use feedback::Feedback;
+use hud::{Anchor, KillFeed, Layout};
+use input::{Bindings, Input};
+use minimap::Minimap;
+use net::{Conditions, Disconnect, Lockstep, Role};
+use rng::{GameRng, Stream};
+use macroquad::miniquad::conf::Platform;
+use scenes::{Context, Scene, Title, Transition};
+use settings::{Gameplay, Settings};
+use stats::Stats;
+use tuning::{Ruleset, Tuning};
+use visibility::Visibility;
+
 const MAP_SIZE_X: usize = 100;
 const MAP_SIZE_Y: usize = 100;
 const MAP_SIZE: usize = MAP_SIZE_X*MAP_SIZE_Y;
-const TILE_SIZE: f32 = 10.0;
-const ROOT_2: f32 = 1.41421356237;
+const ROOT_2: f32 = SQRT_2;
+
+const SCOPE_SETTLE: u32 = 30;
+const SCOPE_ZOOM: f32 = 0.6;
+const SCOPE_LOOK_AHEAD: f32 = 0.7;
+const SNIPER_SPREAD: f32 = 0.2;
+
+const PARRY_WINDOW: u32 = 12;
+const PARRY_COOLDOWN: u32 = 45;
+
+const PICKUP_RADIUS: f32 = 60.0;
+//far enough to cover the screen even scoped and looking ahead
+const SIGHT_RANGE: f32 = 2000.0;
+const FADE_TICKS: u32 = 20;
+//how long anything that failed to load stays listed on screen
+const MISSING_ASSET_TICKS: u32 = 600;
 
 #[macroquad::main(window_conf)]
 async fn main() {
     let mut global = Global::init().await;
 
-    loop {
-        global.tick().await;
+    while global.tick() {
         next_frame().await;
     }
 }
 
+//macroquad asks for this before main runs, so the settings are read once here just for the window
 fn window_conf() -> Conf {
+    let video = Settings::load().video;
+
     Conf {
+        window_width: video.width,
+        window_height: video.height,
+        fullscreen: video.fullscreen,
+        sample_count: video.msaa,
+        high_dpi: video.high_dpi,
+        platform: Platform {
+            swap_interval: Some(if video.vsync {1} else {0}),
+            ..Default::default()
+        },
         ..Default::default()
     }
 }
 
+//the scene stack. only the top scene runs, changes wait for the screen to fade out and happen while it's black
 struct Global {
-    state: Option<Game>,
-    assets: [Texture2D; 2],
-    tick: u32,
+    scenes: Vec<Box<dyn Scene>>,
+    context: Context,
+    pending: Option<Transition>,
+    fade: u32,
+    ticks: u32,
 }
 
 impl Global {
     async fn init() -> Self {
+        let settings = Settings::load();
+        let arguments: Vec<String> = env::args().collect();
+
+        let mut assets = Assets::load();
+        let audio = Audio::load(settings.volumes, &mut assets.missing).await;
+        let mut context = Context {
+            settings,
+            audio,
+            assets,
+            tuning: Tuning::load(),
+            conditions: Rc::new(Cell::new(Conditions::from_args(&arguments))),
+            net_test: arguments.iter().any(|argument| argument == "--net-test"),
+        };
+
+        //closing the window goes through the scenes so a match can be left properly
+        prevent_quit();
+
+        let mut title: Box<dyn Scene> = Box::new(Title::new());
+        title.enter(&mut context);
+
         Self {
-            state: None,
-            assets: [
-                load_texture("src/assets/play_button.png").await.unwrap(), 
-                load_texture("src/assets/logo.png").await.unwrap(),
-            ],
-            tick: 0,
+            scenes: vec![title],
+            context,
+            pending: None,
+            fade: 0,
+            ticks: 0,
         }
     }
 
-    async fn tick(&mut self) {
-        let mut sw = screen_width();
-        let mut sh = screen_height();
-        let mouse_position = mouse_position();
+    //false once there's nothing left to run
+    fn tick(&mut self) -> bool {
+        let (top, below) = match self.scenes.split_last_mut() {
+            Some(split) => split,
+            None => return false,
+        };
+        for scene in below.iter_mut() {
+            scene.covered(&mut self.context);
+        }
+        let mut transition = top.tick(&mut self.context);
+        if is_quit_requested() {
+            transition = top.quit_requested();
+        }
+
+        //anything asked for mid fade is dropped, the first request wins
+        if self.pending.is_none() && !matches!(transition, Transition::None) {
+            self.pending = Some(transition);
+        }
 
-        match self.state {
-            Some(ref mut game) => {
-                clear_background(BLACK);
-                if game.tick() {
-                    self.state = None;
-                    set_camera(&Camera2D::from_display_rect(Rect::new(0.0, 0.0, screen_width(), screen_height())));
+        if self.pending.is_some() {
+            self.fade += 1;
+            if self.fade >= FADE_TICKS {
+                let transition = self.pending.take().unwrap();
+                if !self.apply(transition) {
+                    return false;
                 }
             }
-            None => {
-                if self.tick > 1000000 {
-                    let fade = Color::new(1.0-(self.tick-1000000) as f32/100.0, 1.0-(self.tick-1000000) as f32/100.0, 1.0-(self.tick-1000000) as f32/100.0, 1.0);
-                    clear_background(fade);
-
-                    draw_texture(self.assets[1], sw/2.0-240.0, sh/2.0-340.0, fade);
+        } else {
+            self.fade = self.fade.saturating_sub(1);
+        }
 
-                    draw_texture(self.assets[0], sw/2.0-80.0, sh/2.0+80.0+(self.tick as f32/15.0).cos()*15.0, fade);
+        if self.fade > 0 {
+            set_default_camera();
+            draw_rectangle(0.0, 0.0, screen_width(), screen_height(), Color::new(0.0, 0.0, 0.0, self.fade as f32/FADE_TICKS as f32));
+        }
 
-                    if self.tick > 1000099 {
-                        self.state = Some(Game::init().await);
-                        self.tick = 0;
-                    }
-                } else {
-                    clear_background(WHITE);
-                    draw_texture(self.assets[1], sw/2.0-240.0, sh/2.0-340.0, WHITE);
+        if self.ticks < MISSING_ASSET_TICKS {
+            set_default_camera();
+            self.context.assets.draw_missing(1.0-self.ticks as f32/MISSING_ASSET_TICKS as f32);
+            self.ticks += 1;
+        }
 
-                    draw_texture(self.assets[0], sw/2.0-80.0, sh/2.0+80.0+(self.tick as f32/15.0).cos()*15.0, WHITE);
+        true
+    }
 
-                    if is_mouse_button_released(MouseButton::Left) {
-                        self.tick = 1000000;
-                    }
+    fn apply(&mut self, transition: Transition) -> bool {
+        match transition {
+            Transition::None => {},
+            Transition::Push(mut scene) => {
+                scene.enter(&mut self.context);
+                self.scenes.push(scene);
+            },
+            Transition::Pop => {
+                if let Some(mut scene) = self.scenes.pop() {
+                    scene.exit(&mut self.context);
                 }
-            }
+            },
+            Transition::Replace(mut scene) => {
+                if let Some(mut old) = self.scenes.pop() {
+                    old.exit(&mut self.context);
+                }
+                scene.enter(&mut self.context);
+                self.scenes.push(scene);
+            },
+            Transition::Reset(mut scene) => {
+                while let Some(mut old) = self.scenes.pop() {
+                    old.exit(&mut self.context);
+                }
+                scene.enter(&mut self.context);
+                self.scenes.push(scene);
+            },
+            Transition::Quit => {
+                while let Some(mut old) = self.scenes.pop() {
+                    old.exit(&mut self.context);
+                }
+            },
         }
 
-        self.tick += 1;
+        !self.scenes.is_empty()
     }
 }
 
 fn check_hit(line_endpoint1: Vec2, line_endpoint2: Vec2, radius: f32, center: Vec2) -> bool {
 
-    let line_length = ((line_endpoint2.x - line_endpoint1.x).powf(2.0) + (line_endpoint2.y - line_endpoint1.y).powf(2.0)).sqrt();
+    let line_length = ((line_endpoint2.x - line_endpoint1.x)*(line_endpoint2.x - line_endpoint1.x) + (line_endpoint2.y - line_endpoint1.y)*(line_endpoint2.y - line_endpoint1.y)).sqrt();
 
     let vec_between = Vec2 {
         x: center.x - line_endpoint1.x,
@@ -108,7 +236,7 @@ fn check_hit(line_endpoint1: Vec2, line_endpoint2: Vec2, radius: f32, center: Ve
         y: line_endpoint1.y + ((projection / line_length) * (line_endpoint2.y - line_endpoint1.y)),
     };
 
-    let distance = ((center.x - closest_point.x).powf(2.0) + (center.y - closest_point.y).powf(2.0)).sqrt();
+    let distance = ((center.x - closest_point.x)*(center.x - closest_point.x) + (center.y - closest_point.y)*(center.y - closest_point.y)).sqrt();
 
     if distance <= radius {
         return true;
@@ -117,511 +245,966 @@ fn check_hit(line_endpoint1: Vec2, line_endpoint2: Vec2, radius: f32, center: Ve
     false
 }
 
+fn angle_between(a: f32, b: f32) -> f32 {
+    let difference = (a-b).rem_euclid(2.0*PI);
+    if difference > PI {2.0*PI-difference} else {difference}
+}
+
+fn solid_at(map: &[(f32, bool); MAP_SIZE], position: Vec2) -> bool {
+    if position.x < 0.0 || position.y < 0.0 {
+        return false;
+    }
+
+    let (x, y) = ((position.x/50.0) as usize, (position.y/50.0) as usize);
+    x < MAP_SIZE_X && y < MAP_SIZE_Y && map[x*MAP_SIZE_X+y].1
+}
+
+fn line_of_sight(map: &[(f32, bool); MAP_SIZE], from: Vec2, to: Vec2) -> bool {
+    let steps = (from.distance(to)/25.0).ceil() as u32;
+    for step in 1..steps {
+        if solid_at(map, from.lerp(to, step as f32/steps as f32)) {
+            return false;
+        }
+    }
+
+    true
+}
+
+//what a player can't see: anything out of their sight that isn't theirs. the renderer hides it under the fog, and
+//a hosted match's server leaves it out of what it sends that player so their machine never has it
+fn concealed(visibility: &Visibility, viewer: u32, entity: &Entity) -> bool {
+    match entity.class {
+        Class::Player { id, .. } if id == viewer => false,
+        Class::Projectile(_, _, owner, _) if owner == viewer => false,
+        _ => !visibility.contains(entity.position),
+    }
+}
+
+//whether a player sized box centred here overlaps a wall, the box is one tile across so checking its corners is enough
+fn blocked(map: &[(f32, bool); MAP_SIZE], center: Vec2) -> bool {
+    [Vec2::new(-25.0, -25.0), Vec2::new(25.0, -25.0), Vec2::new(-25.0, 25.0), Vec2::new(25.0, 25.0)].iter().any(|corner| solid_at(map, center+*corner))
+}
+
+fn ray_end(map: &[(f32, bool); MAP_SIZE], from: Vec2, direction: f32, range: f32) -> Vec2 {
+    let step = Vec2::new(direction.exact_cos(), direction.exact_sin())*25.0;
+    let mut end = from;
+    for _ in 0..(range/25.0) as u32 {
+        if solid_at(map, end+step) {
+            break;
+        }
+        end += step;
+    }
+
+    end
+}
+
+//recoil pushes away from the aim but never past the cap along that line, so shots can't be chained into super speed
+fn apply_recoil(velocity: &mut Vec2, direction: f32, strength: f32, cap: f32) {
+    let back = Vec2::new(-direction.exact_cos(), -direction.exact_sin());
+    let along = velocity.dot(back);
+    let pushed = (along+strength).min(cap.max(along));
+    *velocity += back*(pushed-along);
+}
+
+//armor takes its share of a hit until it runs out, the rest goes to health
+fn hurt(health: &mut f32, armor: &mut f32, damage: f32, absorb: f32) {
+    let soaked = (damage*absorb).min(*armor);
+    *armor -= soaked;
+    *health -= damage-soaked;
+}
+
+fn draw_meter(position: Vec2, fill: f32, color: Color) {
+    draw_rectangle(position.x-25.0, position.y, 50.0, 8.0, Color::new(0.0, 0.0, 0.0, 0.6));
+    draw_rectangle(position.x-25.0, position.y, 50.0*fill, 8.0, color);
+}
+
+fn draw_fuse(position: Vec2, fuse: u32, full: u32) {
+    let left = fuse as f32/full as f32;
+    draw_meter(position, left, Color::new(1.0-left, left, 0.0, 1.0));
+}
+
+//the view is 1600 units across its longer side whatever the window size
+fn view_size() -> (f32, f32) {
+    let (sw, sh) = (screen_width(), screen_height());
+    if sh > sw {
+        (sw/sh * 1600.0, 1600.0)
+    } else {
+        (1600.0, sh/sw * 1600.0)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    Duel,
+    Practice,
+}
+
+impl Mode {
+    fn name(&self) -> &'static str {
+        match self {
+            Mode::Duel => "Duel",
+            Mode::Practice => "Practice",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            Mode::Duel => "One life each, last one standing wins",
+            Mode::Practice => "Spawn as many dummies as you like with Q",
+        }
+    }
+}
+
+//what a match is played on and by, online both sides have to agree on it before they start
+#[derive(Clone, Copy, PartialEq)]
+struct Setup {
+    //into map::MAPS
+    map: usize,
+    ruleset: Ruleset,
+}
+
+impl Default for Setup {
+    fn default() -> Self {
+        Self {
+            map: 0,
+            ruleset: Ruleset::Standard,
+        }
+    }
+}
+
 struct Game {
+    mode: Mode,
     map: [(f32, bool); MAP_SIZE],
     entities: Vec<Entity>,
+    //particles and after images, drawn and faded by the renderer only
+    effects: Vec<Entity>,
     player: Player,
-    assets: [Texture2D; 10],
+    tuning: Tuning,
+    rng: GameRng,
+    //steps taken so far
+    tick: u32,
+    //set by the simulation once at most one player is left, unlike the local player's game over screen it's
+    //the same on every machine
+    over: bool,
+    //of the state after the last step, what peers and replays compare to catch a desync
+    checksum: u64,
+    feedback: Feedback,
+    kill_feed: KillFeed,
+    minimap: Minimap,
+    //what the local player can see, none once they're dead and can see everything
+    visibility: Option<Visibility>,
+    //serving a hosted match, what the client's player can see, kept the same way
+    served_visibility: Option<Visibility>,
+    //matches against other machines swap inputs through this, and keep running while someone's paused
+    net: Option<Lockstep>,
+    //presses from frames that didn't queue an input, held for the next one that does
+    unsent: Input,
+    bindings: Bindings,
+    gameplay: Gameplay,
+    stats: Stats,
+    //everyone still in with their gold and whether they're paused, set after each step. the hud reads it rather
+    //than the entities so a hosted client still hears about players it can't see
+    scoreboard: Vec<(u32, u32, bool)>,
+    next_id: u32,
+    textures: Textures,
+    sprites: Sprites,
+    //renderer side, what each player's sprites are doing and anyone still falling over
+    animations: HashMap<u32, PlayerAnimation>,
+    deaths: Vec<(Vec2, f32, Color, Animator)>,
 }
 
 impl Game {
-    async fn init() -> Self {
+    //everything but the textures, enough to run the simulation without a window. the same seed gives the same match
+    fn new(seed: u64) -> Self {
+        Self::with_setup(seed, Setup::default())
+    }
+
+    fn with_setup(seed: u64, setup: Setup) -> Self {
+        let (_, definition) = &map::MAPS[setup.map];
         let mut init = Self {
+            mode: Mode::Duel,
             map: [(0.0, false); MAP_SIZE],
-            entities: vec![Entity::player(), Entity::player()],
+            entities: Vec::new(),
+            effects: Vec::new(),
             player: Player::new(),
-            assets: [
-                load_texture("src/assets/player.png").await.unwrap(), //10x Scale
-                load_texture("src/assets/knife.png").await.unwrap(),
-                load_texture("src/assets/gunner.png").await.unwrap(),
-                load_texture("src/assets/launcher.png").await.unwrap(),
-                load_texture("src/assets/shotgun.png").await.unwrap(),
-                load_texture("src/assets/sprayer.png").await.unwrap(),
-                load_texture("src/assets/sniper.png").await.unwrap(),
-                load_texture("src/assets/slot.png").await.unwrap(),
-                load_texture("src/assets/grenade.png").await.unwrap(),
-                load_texture("src/assets/game_over.png").await.unwrap(),
-            ],
+            tuning: setup.ruleset.tuning(),
+            rng: GameRng::new(seed),
+            tick: 0,
+            over: false,
+            checksum: 0,
+            feedback: Feedback::new(&Settings::default()),
+            kill_feed: KillFeed::new(),
+            minimap: Minimap::new(),
+            visibility: None,
+            served_visibility: None,
+            net: None,
+            unsent: Input::idle(0.0),
+            bindings: Bindings::default(),
+            gameplay: Gameplay::default(),
+            stats: Stats::default(),
+            scoreboard: Vec::new(),
+            next_id: 0,
+            textures: Textures::empty(),
+            sprites: Sprites::empty(),
+            animations: HashMap::new(),
+            deaths: Vec::new(),
         };
 
         for x in 0..MAP_SIZE_X {
             for y in 0..MAP_SIZE_Y {
-                init.map[x*MAP_SIZE_X+y].0 += rand::gen_range(0.0, 0.2);
+                init.map[x*MAP_SIZE_X+y].0 += init.rng.cosmetic.gen_range(0.0, 0.2);
+            }
+        }
+
+        for (x, y, w, h) in definition.walls.iter() {
+            for x in *x..x+w {
+                for y in *y..y+h {
+                    init.map[x*MAP_SIZE_X+y].1 = true;
+                }
             }
         }
 
+        //the local player is always the first one
+        let player = init.new_player();
+        init.entities.push(player);
+        let player = init.new_player();
+        init.entities.push(player);
+
+        for (x, y, kind) in definition.pickups.iter() {
+            init.entities.push(Entity {
+                position: Vec2::new(*x as f32*50.0, *y as f32*50.0),
+                velocity: Vec2::new(0.0, 0.0),
+                class: Class::Pickup { kind: *kind, respawn: 0, claimant: None, claim: 0 },
+            });
+        }
+
         init
     }
 
-    fn tick(&mut self) -> bool {
-        clear_background(BLACK);
-        draw_rectangle_lines(0.0, 0.0, MAP_SIZE_X as f32*50.0, MAP_SIZE_Y as f32*50.0, 20.0, RED);
-        
-        let mut sw = screen_width();
-        let mut sh = screen_height();
-        let mut mouse_position = mouse_position();
+    //a fresh seed for a match nobody else has to agree on
+    fn clock_seed() -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_nanos() as u64)
+    }
 
-        for x in 0..MAP_SIZE_X {
-            for y in 0..MAP_SIZE_Y {
-                draw_rectangle(x as f32*50.0, y as f32*50.0, 50.0, 50.0, Color::new(1.0, 1.0, 1.0, self.map[x*MAP_SIZE_X+y].0))
+    fn init(settings: &Settings, assets: &Assets, mode: Mode, setup: Setup, seed: u64) -> Self {
+        let mut init = Self::with_setup(seed, setup);
+        init.mode = mode;
+        init.feedback = Feedback::new(settings);
+        init.bindings = settings.controls;
+        init.gameplay = settings.gameplay;
+        init.textures = assets.textures;
+        init.sprites = assets.sprites.clone();
+
+        init
+    }
+
+    //plays this match against another machine, as the player with this id. both sides have to have started from
+    //the same seed
+    fn connect(&mut self, local: u32, net: Lockstep) {
+        self.player.id = local;
+        self.player.index = self.find_player(local);
+        self.net = Some(net);
+    }
+
+    fn networked(&self) -> bool {
+        self.net.is_some()
+    }
+
+    fn new_player(&mut self) -> Entity {
+        self.stats.player(self.next_id);
+        self.next_id += 1;
+        Entity::player(&self.map, self.next_id-1, &mut self.rng.gameplay)
+    }
+
+    //a paused player sends idle input. offline there's nobody to keep playing against so the match stops
+    //outright, a networked one keeps going and the others are told through the input
+    fn tick(&mut self, audio: &mut Audio, paused: bool) -> bool {
+        let halted = paused && !self.networked();
+
+        if !paused && self.mode == Mode::Practice && is_key_pressed(KeyCode::Q) {
+            let player = self.new_player();
+            self.entities.push(player);
+        }
+
+        let input = match self.player.index.map(|index| &self.entities[index]) {
+            Some(Entity { class: Class::Player { direction, .. }, .. }) if paused => Some(Input { paused: true, ..Input::idle(*direction) }),
+            Some(Entity { position, .. }) => Some(Input::read(&self.player.camera, *position, &self.bindings)),
+            None => None,
+        };
+
+        let events = self.advance(input, halted, get_time());
+        self.player.index = self.find_player(self.player.id);
+        //only rebuilt once the eye moves, standing still sees the same as last frame
+        let eye = self.player.index.map(|index| self.entities[index].position);
+        if eye != self.visibility.as_ref().map(Visibility::eye) {
+            self.visibility = eye.map(|eye| Visibility::new(&self.map, eye, SIGHT_RANGE));
+        }
+        if !halted {
+            self.minimap.see(&self.entities, self.player.index, &self.visibility);
+        }
+
+        for event in events.iter() {
+            if let GameEvent::Kill { victim, .. } = event {
+                if *victim == self.player.id && self.player.game.is_none() {
+                    self.player.game = Some(0);
+                }
             }
         }
 
-        let mut appendlist = Vec::new();
-        let mut deletelist = Vec::new();
-        let mut scope = false;
+        //nobody else left standing
+        if self.player.game.is_none() && self.over {
+            self.player.game = Some(0);
+            self.player.won = true;
+        }
+        self.spawn_effects(&events);
+
+        let listener = self.player.camera.target;
+        audio.events(&events, listener, self.player.id);
+        audio.world(&self.entities, listener);
+        self.feedback.events(&events, self.player.id, listener);
+        self.kill_feed.events(&events);
+
+        self.draw();
+        self.feedback.tick();
+        self.kill_feed.tick();
+
+        if let Some(game) = self.player.game {
+            if game > 400 {
+                return true;
+            }
+
+            if !halted {
+                self.player.game = Some(game+1);
+            }
+        }
 
-        if is_key_pressed(KeyCode::Q) {
-            appendlist.push(Entity::player())
+        false
+    }
+
+    //entity indices shift whenever something is removed, so the local player is looked up by id each tick
+    fn find_player(&self, id: u32) -> Option<usize> {
+        self.entities.iter().position(|entity| matches!(entity.class, Class::Player { id: player, .. } if player == id))
+    }
+
+    //picks up changes made from the pause menu
+    fn apply_settings(&mut self, settings: &Settings) {
+        self.bindings = settings.controls;
+        self.gameplay = settings.gameplay;
+        self.feedback.apply_settings(settings);
+    }
+
+    //walk away from the match, it counts as a loss. anyone on the other end is told it's theirs
+    fn forfeit(&mut self) {
+        if let Some(ref mut net) = self.net {
+            net.leave();
+        }
+        if self.player.game.is_none() {
+            self.player.game = Some(0);
+            self.player.won = false;
+        }
+    }
+
+    //moves the match on with the local player's input, none once they're dead. offline that's one step unless it's
+    //halted, over the network it's however many ticks have everyone's input in, which can be none
+    fn advance(&mut self, input: Option<Input>, halted: bool, now: f64) -> Vec<GameEvent> {
+        let mut net = match self.net.take() {
+            Some(net) => net,
+            None if halted => return Vec::new(),
+            None => {
+                let events = self.step(&input.map(|input| vec![(self.player.id, input)]).unwrap_or_default());
+                self.stats.events(&events);
+                return events;
+            },
+        };
+
+        //the dead still send something every tick or the other side would wait on them forever
+        if net.wants_input() {
+            let mut input = input.unwrap_or_else(|| Input::idle(0.0));
+            input.carry(&self.unsent);
+            self.unsent = Input::idle(0.0);
+            net.push(input);
+        } else if let Some(input) = input {
+            self.unsent.carry(&input);
+        }
+        net.poll(now);
+
+        //the other side quitting hands us the match, losing them to a timeout just ends it
+        if let (Some(disconnect), None) = (net.disconnect, self.player.game) {
+            self.player.game = Some(0);
+            self.player.won = disconnect == Disconnect::Left;
         }
 
+        let mut events = Vec::new();
+        if net.role == Role::Client {
+            //the server steps the match, we only take on what it sends. our counts only see what we're shown,
+            //the server's replace them once the match is decided
+            for bytes in net.take_events() {
+                events.extend(snapshot::decode_events(&bytes));
+            }
+            if !self.over {
+                self.stats.events(&events);
+            }
+            if let Some(state) = net.take_state() {
+                self.apply_snapshot(&state);
+            }
+            self.net = Some(net);
+            return events;
+        }
+
+        //a tick behind catches up one extra a frame
+        for _ in 0..2 {
+            match net.next() {
+                Some(inputs) => {
+                    let stepped = self.step(&inputs);
+                    net.stepped(self.checksum);
+                    if net.wants_dump() {
+                        net.dump(self.state(true).fields());
+                    }
+                    if net.role == Role::Server {
+                        let (state, revealed) = self.serve(net.remote, &stepped);
+                        net.publish(state, revealed);
+                    }
+                    events.extend(stepped);
+                },
+                None => break,
+            }
+        }
+        self.stats.events(&events);
+
+        self.net = Some(net);
+        events
+    }
+
+    //advances the match by one tick. nothing in here draws or reads devices, players without an input stand still
+    fn step(&mut self, inputs: &[(u32, Input)]) -> Vec<GameEvent> {
+        let mut appendlist = Vec::new();
+        let mut deletelist = Vec::new();
+        let mut damagelist: Vec<(usize, f32, Vec2, Option<u32>, WeaponType, Vec2)> = Vec::new();
+        let mut pickuplist: Vec<(usize, PickupKind)> = Vec::new();
+        let mut goldlist: Vec<usize> = Vec::new();
+        let mut events = Vec::new();
+
         let entities = self.entities.to_vec();
 
         for (count, entity) in self.entities.iter_mut().enumerate() {
             match entity.class {
-                Class::Player { ref mut weapon, ref mut direction, ref mut health } => {
-                    let recoil = if weapon.last_fire <= 6 {
-                        6-weapon.last_fire
-                    } else {
-                        0
-                    };
+                Class::Player { id, ref mut weapon, ref mut direction, ref mut health, ref mut last_dash, ref mut armor, ref mut damage_boost, ref mut speed_boost, ref mut last_attacker, ref mut paused, .. } => {
+                    let outgoing = if *damage_boost > 0 {self.tuning.damage_boost} else {1.0};
 
                     if *health < 100.0 {
                         *health += 0.02;
                     }
-                    
-                    match weapon.class {
-                        WeaponType::Knife(side) => {
-                            draw_texture_ex(self.assets[1], entity.position.x+20.0-recoil as f32, entity.position.y-20.0, WHITE, DrawTextureParams {rotation: *direction+if side {1.0} else {-1.0}, pivot: Some(entity.position), flip_y: side, ..Default::default()});
+
+                    //bullet physics
+                    let immune = *last_dash < self.tuning.dash_immunity;
+                    for (index, hitbox) in entities.iter().enumerate() {
+                        if let Class::Projectile(weapontype, _, owner, alt) = hitbox.class {
+                            if !immune && (check_hit(hitbox.position, Vec2::new(hitbox.position.x+hitbox.velocity.x, hitbox.position.y+hitbox.velocity.y), 60.0, entity.position) || hitbox.position.distance(entity.position) < 60.0) && owner != id && line_of_sight(&self.map, hitbox.position, entity.position) {
+                                if !deletelist.contains(&index) {deletelist.push(index);}
+
+                                let incoming = hitbox.position-entity.position;
+                                if weapon.parry > 0 && matches!(weapon.class, WeaponType::Knife(_)) && angle_between(*direction, incoming.y.exact_atan2(incoming.x)) < PI/2.0 {
+                                    //parried shots go back the way the knife is facing
+                                    appendlist.push(Entity {
+                                        position: Vec2::new(entity.position.x+direction.exact_cos()*70.0, entity.position.y+direction.exact_sin()*70.0),
+                                        velocity: Vec2::new(direction.exact_cos(), direction.exact_sin())*hitbox.velocity.length(),
+                                        class: Class::Projectile(weapontype, 0, id, alt),
+                                    });
+                                } else {
+                                    let damage = match (weapontype, alt) {
+                                        (WeaponType::Shotgun, true) => self.tuning.slug_damage,
+                                        (WeaponType::Knife(_), _) => self.tuning.knife_throw_damage,
+                                        (WeaponType::Sniper, _) => 25.0,
+                                        (WeaponType::Gunner, _) => 8.0,
+                                        (WeaponType::Shotgun, _) => 3.0,
+                                        (WeaponType::Sprayer, _) => 7.0,
+                                        _ => 0.0,
+                                    };
+                                    //whoever fired it might have picked up a damage boost since, or be gone
+                                    let (boost, source) = match entities.iter().find(|other| matches!(other.class, Class::Player { id, .. } if id == owner)) {
+                                        Some(Entity { position, class: Class::Player { damage_boost, .. }, .. }) => (if *damage_boost > 0 {self.tuning.damage_boost} else {1.0}, *position),
+                                        _ => (1.0, hitbox.position-hitbox.velocity),
+                                    };
+                                    hurt(health, armor, damage*boost, self.tuning.armor_absorb);
+                                    *last_attacker = Some(owner);
+                                    events.push(GameEvent::Hit { target: id, attacker: Some(owner), weapon: weapontype, damage: damage*boost, position: entity.position, source });
+
+                                    entity.velocity.x += hitbox.velocity.x/60.0;
+                                    entity.velocity.y += hitbox.velocity.y/60.0;
+                                }
+                            }
                         }
 
-                        WeaponType::Sniper => {
-                            draw_texture_ex(self.assets[6], entity.position.x+20.0-recoil as f32, entity.position.y-20.0, WHITE, DrawTextureParams {rotation: *direction, pivot: Some(entity.position), ..Default::default()});
-                        },
+                        //grenades only hurt players they have line of sight to
+                        if let Class::Grenade { fuse: 0, owner, .. } = hitbox.class {
+                            let distance = hitbox.position.distance(entity.position);
+                            if distance < self.tuning.grenade_radius && line_of_sight(&self.map, hitbox.position, entity.position) {
+                                let direction_difference = if hitbox.position.x-entity.position.x > 0.0 {((hitbox.position.y-entity.position.y)/(hitbox.position.x-entity.position.x)).exact_atan()} else {((hitbox.position.y-entity.position.y)/(hitbox.position.x-entity.position.x)).exact_atan()+PI};
+                                //the thrower's damage boost counts if they've still got it
+                                let boost = match entities.iter().find(|other| matches!(other.class, Class::Player { id, .. } if id == owner)) {
+                                    Some(Entity { class: Class::Player { damage_boost, .. }, .. }) if *damage_boost > 0 => self.tuning.damage_boost,
+                                    _ => 1.0,
+                                };
+                                let damage = (self.tuning.grenade_damage*(1.0-distance/self.tuning.grenade_radius)).max(0.0)*boost;
+                                hurt(health, armor, damage, self.tuning.armor_absorb);
+                                //your own grenade doesn't count as a hit or take the kill off whoever hurt you last
+                                let attacker = Some(owner).filter(|owner| *owner != id);
+                                if attacker.is_some() {
+                                    *last_attacker = attacker;
+                                }
+                                events.push(GameEvent::Hit { target: id, attacker, weapon: WeaponType::Grenade, damage, position: entity.position, source: hitbox.position });
+                                entity.velocity.x -= direction_difference.exact_cos()*((self.tuning.grenade_radius-distance)/6.0);
+                                entity.velocity.y -= direction_difference.exact_sin()*((self.tuning.grenade_radius-distance)/6.0);
+                            }
+                        }
 
-                        WeaponType::Gunner => {
-                            draw_texture_ex(self.assets[2], entity.position.x+20.0-recoil as f32, entity.position.y-20.0, WHITE, DrawTextureParams {rotation: *direction, pivot: Some(entity.position), ..Default::default()});
-                        },
+                    }
 
-                        WeaponType::Shotgun => {
-                            draw_texture_ex(self.assets[4], entity.position.x+20.0-recoil as f32, entity.position.y-20.0, WHITE, DrawTextureParams {rotation: *direction, pivot: Some(entity.position), ..Default::default()});
-                        },
+                    weapon.parry = weapon.parry.saturating_sub(1);
 
-                        WeaponType::Sprayer => {
-                            draw_texture_ex(self.assets[5], entity.position.x+20.0-recoil as f32, entity.position.y-20.0, WHITE, DrawTextureParams {rotation: *direction, pivot: Some(entity.position), ..Default::default()});
-                        },
+                    let input = inputs.iter().find(|(player, _)| *player == id).map_or(Input::idle(*direction), |(_, input)| *input);
 
-                        WeaponType::Grenade => {
-                            draw_texture_ex(self.assets[3], entity.position.x+20.0-recoil as f32, entity.position.y-20.0, WHITE, DrawTextureParams {rotation: *direction, pivot: Some(entity.position), ..Default::default()});
-                        },
+                    if input.paused != *paused {
+                        *paused = input.paused;
+                        events.push(GameEvent::Paused { player: id, paused: input.paused });
                     }
 
-                    //bullet physics
-                    let mut preventmultikill = true;
-                    for (index, hitbox) in entities.iter().enumerate() {
-                        if let Class::Projectile(weapontype, tick, owner) = hitbox.class {
-                            let distance = hitbox.position.distance(entity.position);
-                            match weapontype {
-                                WeaponType::Knife(_side) => {
-                                    if count != owner.unwrap() && distance < 60.0 {
-                                        *health -= 6.0-tick as f32/60.0;
-                                        if !deletelist.contains(&index) {deletelist.push(index);}
-                                        entity.velocity.x += hitbox.velocity.x*0.3;
-                                        entity.velocity.y += hitbox.velocity.y*0.3;
-                                    }
-                                }
+                    *direction = input.aim;
 
-                                WeaponType::Grenade => {
-                                    if tick == 80 {
-                                        if distance < 300.0 {
-                                            let direction_difference = if hitbox.position.x-entity.position.x > 0.0 {((hitbox.position.y-entity.position.y)/(hitbox.position.x-entity.position.x)).atan()} else {((hitbox.position.y-entity.position.y)/(hitbox.position.x-entity.position.x)).atan()+PI};
-                                            *health -= 50.0-distance as f32/6.0;
-                                            entity.velocity.x -= direction_difference.cos()*((300.0-distance as f32)/6.0);
-                                            entity.velocity.y -= direction_difference.sin()*((300.0-distance as f32)/6.0);
-                                        }
-                                    }
-                                },
+                    //weapon change
+                    if !self.over {
+                        if let Some(class) = input.weapon {
+                            if class.slot_index() != weapon.class.slot_index() {
+                                events.push(GameEvent::WeaponSwitched { player: id });
+                            }
+                            weapon.class = class;
+                        }
 
-                                _ => {
-                                    if (check_hit(hitbox.position, Vec2::new(hitbox.position.x+hitbox.velocity.x, hitbox.position.y+hitbox.velocity.y), 60.0, entity.position) || hitbox.position.distance(entity.position) < 60.0) && count != owner.unwrap() {
-                                        *health -= match weapontype {
-                                            WeaponType::Sniper => 25.0,
-                                            WeaponType::Gunner => 8.0,
-                                            WeaponType::Shotgun => 3.0,
-                                            WeaponType::Sprayer => 7.0,
-                                            _ => 0.0,
-                                        };
+                        //switching away from a cooked grenade drops it
+                        if !matches!(weapon.class, WeaponType::Grenade) {
+                            if let Some(cook) = weapon.cook.take() {
+                                appendlist.push(Entity {
+                                    position: entity.position,
+                                    velocity: Vec2::new(0.0, 0.0),
+                                    class: Class::Grenade { fuse: self.tuning.grenade_fuse.saturating_sub(cook), height: 0.0, lift: 0.0, owner: id },
+                                });
+                            }
+                        }
 
-                                        if !deletelist.contains(&index) {deletelist.push(index);} 
 
-                                        appendlist.push(Entity {
-                                            position: entity.position,
-                                            velocity: Vec2::new(rand::gen_range(0.0, 2.0*PI).cos()*15.0, rand::gen_range(0.0, 2.0*PI).sin()*15.0),
-                                            class: Class::Particle(RED, 20)
-                                        });
-                                        //draw_line(hitbox.position.x, hitbox.position.y, hitbox.velocity.x/3.0, hitbox.velocity.y/3.0, 10.0, WHITE);
-                                        entity.velocity.x += hitbox.velocity.x/60.0;
-                                        entity.velocity.y += hitbox.velocity.y/60.0;
-                                    }
-                                },
-                            }
-                            
-                            
+                        if !matches!(weapon.class, WeaponType::Sniper) {
+                            weapon.scoped = 0;
                         }
-                        
-                    }
-                    
-                    if self.player.index == count {
-                        let mouse_diference = Vec2::new(mouse_position.0 - sw/2.0, mouse_position.1 - sh/2.0);
-                        *direction = if mouse_diference.x > 0.0 {(mouse_diference.y/mouse_diference.x).atan()} else if mouse_diference.x < 0.0 {PI+(mouse_diference.y/mouse_diference.x).atan()} else {(mouse_diference.y/mouse_diference.x).atan()};
-                        
-                        if is_quit_requested() {
-                            deletelist.push(count);
+
+                        if !matches!(weapon.class, WeaponType::Gunner) {
+                            weapon.burst = 0;
                         }
 
-                        //weapon change
-                        if self.player.game.is_none() {
-                            if is_key_down(KeyCode::Key1) {
-                                weapon.class = WeaponType::Knife(true);
-                            } else if is_key_down(KeyCode::Key2) {
-                                weapon.class = WeaponType::Gunner;
-                            } else if is_key_down(KeyCode::Key3) {
-                                weapon.class = WeaponType::Grenade;
-                            } else if is_key_down(KeyCode::Key4) {
-                                weapon.class = WeaponType::Shotgun;
-                            } else if is_key_down(KeyCode::Key5) {
-                                weapon.class = WeaponType::Sprayer;
-                            } else if is_key_down(KeyCode::Key6) {
-                                weapon.class = WeaponType::Sniper;
-                            }
-                        
-
-                            //shot detection
-                            match weapon.class {
-                                WeaponType::Knife(ref mut side) => {
-                                    if is_mouse_button_pressed(MouseButton::Left) && weapon.last_fire > 10 {
-                                        *side = !*side;
-                                        for rotation in -5..5 {
-                                            appendlist.push(Entity { 
-                                                position: Vec2::new(((rotation as f32)/30.0*PI+*direction).cos()*80.0+entity.position.x, ((rotation as f32)/30.0*PI+*direction).sin()*80.0+entity.position.y),
-                                                velocity: Vec2::new(((rotation as f32)/30.0*PI+*direction).cos()*2.0+entity.velocity.x, ((rotation as f32)/30.0*PI+*direction).sin()*2.0+entity.velocity.y),
-                                                class: Class::Projectile(WeaponType::Knife(*side), 0, Some(self.player.index)),
-                                            })
+                        //shot detection
+                        let (cooldown, alt_cooldown) = (weapon.class.cooldown(), self.tuning.alt_cooldown_for(weapon.class));
+                        match weapon.class {
+                            WeaponType::Knife(ref mut side) => {
+                                if input.fire_pressed && weapon.last_fire > cooldown {
+                                    *side = !*side;
+
+                                    //one hit test per swing, against the closest player inside the arc that isn't behind a wall
+                                    let mut target: Option<(usize, f32)> = None;
+                                    for (index, other) in entities.iter().enumerate() {
+                                        if let Class::Player { .. } = other.class {
+                                            let offset = other.position-entity.position;
+                                            if index != count && offset.length() < self.tuning.knife_range && angle_between(*direction, offset.y.exact_atan2(offset.x)) < self.tuning.knife_arc && target.is_none_or(|(_, distance)| offset.length() < distance) && line_of_sight(&self.map, entity.position, other.position) {
+                                                target = Some((index, offset.length()));
+                                            }
                                         }
-                                        weapon.last_fire = 0;
                                     }
-                                    
-                                },
-
-                                WeaponType::Sniper => {
-                                    scope = true;
-                                    if is_mouse_button_pressed(MouseButton::Left) && weapon.last_fire > 30 {
-                                        appendlist.push(Entity { 
-                                            position: Vec2::new(entity.position.x+direction.cos()*90.0, entity.position.y+direction.sin()*90.0),
-                                            velocity: Vec2::new(entity.velocity.x+direction.cos()*150.0, entity.velocity.y+direction.sin()*150.0),
-                                            class: Class::Projectile(WeaponType::Sniper, 0, Some(self.player.index)),
-                                        });
 
-                                        for rotation in -5..6 {
-                                            appendlist.push(Entity { 
-                                                position: Vec2::new(direction.cos()*100.0+entity.position.x+((rotation as f32)/10.0*PI+*direction).cos()*10.0, direction.sin()*100.0+entity.position.y+((rotation as f32)/10.0*PI+*direction).cos()*10.0),
-                                                velocity: Vec2::new(((rotation as f32)/10.0*PI+*direction).cos()*2.0+entity.velocity.x, ((rotation as f32)/10.0*PI+*direction).sin()*2.0+entity.velocity.y),
-                                                class: Class::Particle(ORANGE, 15),
-                                            })
+                                    if let Some((index, _)) = target {
+                                        if let Class::Player { weapon: other_weapon, direction: other_direction, .. } = entities[index].class {
+                                            let offset = entities[index].position-entity.position;
+                                            let facing = offset.y.exact_atan2(offset.x);
+                                            if other_weapon.parry > 0 && matches!(other_weapon.class, WeaponType::Knife(_)) && angle_between(other_direction, facing) > PI/2.0 {
+                                                //blocked, the attacker gets pushed off instead
+                                                entity.velocity.x -= direction.exact_cos()*self.tuning.knife_lunge;
+                                                entity.velocity.y -= direction.exact_sin()*self.tuning.knife_lunge;
+                                            } else {
+                                                //the target facing away from us means we are behind them
+                                                let backstab = angle_between(other_direction, facing) < PI/3.0;
+                                                damagelist.push((index, (if backstab {self.tuning.knife_damage*self.tuning.knife_backstab} else {self.tuning.knife_damage})*outgoing, Vec2::new(direction.exact_cos(), direction.exact_sin())*8.0, Some(id), WeaponType::Knife(*side), entity.position));
+                                                entity.velocity.x += direction.exact_cos()*self.tuning.knife_lunge;
+                                                entity.velocity.y += direction.exact_sin()*self.tuning.knife_lunge;
+                                            }
                                         }
-                                        weapon.last_fire = 0;
-                                        
-                                        entity.position.x -= direction.cos()*10.0;
-                                        entity.position.y -= direction.sin()*10.0;
                                     }
-                                },
-
-                                WeaponType::Gunner => {
-                                    if is_mouse_button_down(MouseButton::Left) && weapon.last_fire > 10 {
-                                        appendlist.push(Entity { 
-                                            position: Vec2::new(entity.position.x+direction.cos()*90.0, entity.position.y+direction.sin()*90.0),
-                                            velocity: Vec2::new(entity.velocity.x+direction.cos()*120.0, entity.velocity.y+direction.sin()*120.0),
-                                            class: Class::Projectile(WeaponType::Gunner, 0, Some(self.player.index)),
-                                        });
 
-                                        for rotation in -2..3 {
-                                            appendlist.push(Entity { 
-                                                position: Vec2::new(direction.cos()*100.0+entity.position.x+((rotation as f32)/10.0*PI+*direction).cos()*10.0, direction.sin()*100.0+entity.position.y+((rotation as f32)/10.0*PI+*direction).cos()*10.0),
-                                                velocity: Vec2::new(((rotation as f32)/10.0*PI+*direction).cos()*2.0+entity.velocity.x, ((rotation as f32)/10.0*PI+*direction).sin()*2.0+entity.velocity.y),
-                                                class: Class::Particle(ORANGE, 15),
-                                            })
-                                        }
-                                        weapon.last_fire = 0;
-                                        entity.velocity.x -= direction.cos()*5.0;
-                                        entity.velocity.y -= direction.sin()*5.0;
-                                    }
-                                    
-                                },
+                                    events.push(GameEvent::ShotFired { player: id, weapon: WeaponType::Knife(*side), alt: false, position: entity.position, direction: *direction, velocity: entity.velocity });
+                                    weapon.last_fire = 0;
+                                } else if input.parry && weapon.last_fire > PARRY_COOLDOWN {
+                                    weapon.parry = PARRY_WINDOW;
+                                    weapon.last_fire = 0;
+                                }
 
-                                WeaponType::Shotgun => {
-                                    if is_mouse_button_pressed(MouseButton::Left) && weapon.last_fire > 30 {
-                                        for rotation in -5..6 {
-                                            appendlist.push(Entity { 
-                                                position: Vec2::new(entity.position.x+direction.cos()*90.0, entity.position.y+direction.sin()*90.0),
-                                                velocity: Vec2::new(entity.velocity.x+((rotation as f32)/30.0*PI+*direction).cos()*70.0, entity.velocity.y+((rotation as f32)/30.0*PI+*direction).sin()*70.0),
-                                                class: Class::Projectile(WeaponType::Shotgun, 0, Some(self.player.index)),
-                                            });
-                                        }
+                                //alt fire throws the knife
+                                if input.alt_fire_pressed && weapon.last_alt_fire > alt_cooldown {
+                                    appendlist.push(Entity {
+                                        position: Vec2::new(entity.position.x+direction.exact_cos()*70.0, entity.position.y+direction.exact_sin()*70.0),
+                                        velocity: Vec2::new(entity.velocity.x+direction.exact_cos()*self.tuning.knife_throw_speed, entity.velocity.y+direction.exact_sin()*self.tuning.knife_throw_speed),
+                                        class: Class::Projectile(WeaponType::Knife(*side), 0, id, true),
+                                    });
+                                    events.push(GameEvent::ShotFired { player: id, weapon: WeaponType::Knife(*side), alt: true, position: entity.position, direction: *direction, velocity: entity.velocity });
+                                    weapon.last_alt_fire = 0;
+                                }
 
-                                        for rotation in -2..3 {
-                                            appendlist.push(Entity { 
-                                                position: Vec2::new(direction.cos()*100.0+entity.position.x+((rotation as f32)/10.0*PI+*direction).cos()*10.0, direction.sin()*100.0+entity.position.y+((rotation as f32)/10.0*PI+*direction).cos()*10.0),
-                                                velocity: Vec2::new(((rotation as f32)/10.0*PI+*direction).cos()*2.0+entity.velocity.x, ((rotation as f32)/10.0*PI+*direction).sin()*2.0+entity.velocity.y),
-                                                class: Class::Particle(ORANGE, 15),
-                                            })
-                                        }
-                                        weapon.last_fire = 0;
+                            },
 
-                                        entity.velocity.x -= direction.cos()*12.0;
-                                        entity.velocity.y -= direction.sin()*12.0;
-                                    }
-                                    
-                                },
-
-                                WeaponType::Sprayer => {
-                                    if is_mouse_button_down(MouseButton::Left) && weapon.last_fire > 3 {
-                                        let shotdirection = *direction+PI*rand::gen_range(-0.1, 0.1);
-                                        appendlist.push(Entity { 
-                                            position: Vec2::new(entity.position.x+direction.cos()*100.0, entity.position.y+direction.sin()*100.0),
-                                            velocity: Vec2::new(entity.velocity.x+shotdirection.cos()*90.0, entity.velocity.y+shotdirection.sin()*90.0),
-                                            class: Class::Projectile(WeaponType::Shotgun, 0, Some(self.player.index)),
-                                        });
+                            WeaponType::Sniper => {
+                                //alt fire scopes in
+                                if input.alt_fire {
+                                    weapon.scoped += 1;
+                                } else {
+                                    weapon.scoped = 0;
+                                }
 
+                                if input.fire_pressed && weapon.last_fire > cooldown {
+                                    //firing before the scope settles throws the shot off
+                                    let spread = SNIPER_SPREAD*(1.0-weapon.scoped.min(SCOPE_SETTLE) as f32/SCOPE_SETTLE as f32);
+                                    let shotdirection = *direction+self.rng.gameplay.gen_range(-spread, spread);
+                                    appendlist.push(Entity {
+                                        position: Vec2::new(entity.position.x+direction.exact_cos()*90.0, entity.position.y+direction.exact_sin()*90.0),
+                                        velocity: Vec2::new(entity.velocity.x+shotdirection.exact_cos()*150.0, entity.velocity.y+shotdirection.exact_sin()*150.0),
+                                        class: Class::Projectile(WeaponType::Sniper, 0, id, false),
+                                    });
 
-                                        for rotation in -2..3 {
-                                            appendlist.push(Entity { 
-                                                position: Vec2::new(direction.cos()*110.0+entity.position.x+((rotation as f32)/10.0*PI+*direction).cos()*10.0, direction.sin()*110.0+entity.position.y+((rotation as f32)/10.0*PI+*direction).cos()*10.0),
-                                                velocity: Vec2::new(((rotation as f32)/10.0*PI+*direction).cos()*2.0+entity.velocity.x, ((rotation as f32)/10.0*PI+*direction).sin()*2.0+entity.velocity.y),
-                                                class: Class::Particle(ORANGE, 15),
-                                            })
-                                        }
-                                        weapon.last_fire = 0;
-                                        entity.velocity.x -= direction.cos()*2.0;
-                                        entity.velocity.y -= direction.sin()*2.0;
+                                    events.push(GameEvent::ShotFired { player: id, weapon: WeaponType::Sniper, alt: false, position: entity.position, direction: *direction, velocity: entity.velocity });
+                                    weapon.last_fire = 0;
+
+                                    apply_recoil(&mut entity.velocity, *direction, self.tuning.recoil_for(weapon.class), self.tuning.recoil_cap);
+                                }
+                            },
+
+                            WeaponType::Gunner => {
+                                //alt fire queues a quick burst
+                                if input.alt_fire_pressed && weapon.burst == 0 && weapon.last_alt_fire > alt_cooldown {
+                                    weapon.burst = self.tuning.burst_shots;
+                                    weapon.last_alt_fire = 0;
+                                }
+                                let burst = weapon.burst > 0 && weapon.last_alt_fire % self.tuning.burst_gap == 0;
+
+                                if (input.fire && weapon.last_fire > cooldown) || burst {
+                                    let speed = if burst {140.0} else {120.0};
+                                    appendlist.push(Entity {
+                                        position: Vec2::new(entity.position.x+direction.exact_cos()*90.0, entity.position.y+direction.exact_sin()*90.0),
+                                        velocity: Vec2::new(entity.velocity.x+direction.exact_cos()*speed, entity.velocity.y+direction.exact_sin()*speed),
+                                        class: Class::Projectile(WeaponType::Gunner, 0, id, burst),
+                                    });
+                                    if burst {
+                                        weapon.burst -= 1;
                                     }
 
-                                    
+                                    events.push(GameEvent::ShotFired { player: id, weapon: WeaponType::Gunner, alt: burst, position: entity.position, direction: *direction, velocity: entity.velocity });
+                                    weapon.last_fire = 0;
+                                    apply_recoil(&mut entity.velocity, *direction, self.tuning.recoil_for(weapon.class), self.tuning.recoil_cap);
                                 }
 
-                                WeaponType::Grenade => {
-                                    if is_mouse_button_pressed(MouseButton::Left) && weapon.last_fire > 70 {
-                                        appendlist.push(Entity { 
-                                            position: Vec2::new(entity.position.x+direction.cos()*90.0, entity.position.y+direction.sin()*90.0),
-                                            velocity: Vec2::new(entity.velocity.x+direction.cos()*40.0, entity.velocity.y+direction.sin()*40.0),
-                                            class: Class::Projectile(WeaponType::Grenade, 0, Some(self.player.index)),
+                            },
+
+                            WeaponType::Shotgun => {
+                                //alt fire chokes the barrel down to a single slug
+                                let slug = input.alt_fire_pressed && weapon.last_alt_fire > alt_cooldown;
+                                if (input.fire_pressed && weapon.last_fire > cooldown) || slug {
+                                    if slug {
+                                        appendlist.push(Entity {
+                                            position: Vec2::new(entity.position.x+direction.exact_cos()*90.0, entity.position.y+direction.exact_sin()*90.0),
+                                            velocity: Vec2::new(entity.velocity.x+direction.exact_cos()*self.tuning.slug_speed, entity.velocity.y+direction.exact_sin()*self.tuning.slug_speed),
+                                            class: Class::Projectile(WeaponType::Shotgun, 0, id, true),
                                         });
+                                        weapon.last_alt_fire = 0;
+                                    } else {
+                                        for rotation in -5..6 {
+                                            appendlist.push(Entity {
+                                                position: Vec2::new(entity.position.x+direction.exact_cos()*90.0, entity.position.y+direction.exact_sin()*90.0),
+                                                velocity: Vec2::new(entity.velocity.x+((rotation as f32)/30.0*PI+*direction).exact_cos()*70.0, entity.velocity.y+((rotation as f32)/30.0*PI+*direction).exact_sin()*70.0),
+                                                class: Class::Projectile(WeaponType::Shotgun, 0, id, false),
+                                            });
+                                        }
+                                    }
+
+                                    events.push(GameEvent::ShotFired { player: id, weapon: WeaponType::Shotgun, alt: slug, position: entity.position, direction: *direction, velocity: entity.velocity });
+                                    weapon.last_fire = 0;
+
+                                    apply_recoil(&mut entity.velocity, *direction, self.tuning.recoil_for(weapon.class), self.tuning.recoil_cap);
+                                }
 
-                                        for rotation in -2..3 {
-                                            appendlist.push(Entity { 
-                                                position: Vec2::new(direction.cos()*110.0+entity.position.x+((rotation as f32)/10.0*PI+*direction).sin()*10.0, direction.sin()*110.0+entity.position.y+((rotation as f32)/10.0*PI+*direction).sin()*10.0),
-                                                velocity: Vec2::new(((rotation as f32)/10.0*PI+*direction).cos()*5.0+entity.velocity.x, ((rotation as f32)/10.0*PI+*direction).sin()*5.0+entity.velocity.y),
-                                                class: Class::Particle(GREEN, 15),
-                                            })
+                            },
+
+                            WeaponType::Sprayer => {
+                                //alt fire vents the built up heat as a short blast of steam
+                                if input.alt_fire_pressed && weapon.heat > 0 && weapon.last_alt_fire > alt_cooldown {
+                                    for (index, other) in entities.iter().enumerate() {
+                                        if let Class::Player { .. } = other.class {
+                                            let offset = other.position-entity.position;
+                                            if index != count && offset.length() < self.tuning.vent_range && angle_between(*direction, offset.y.exact_atan2(offset.x)) < PI/4.0 && line_of_sight(&self.map, entity.position, other.position) {
+                                                damagelist.push((index, weapon.heat as f32*0.3*outgoing, offset.normalize()*15.0, Some(id), WeaponType::Sprayer, entity.position));
+                                            }
                                         }
-                                        weapon.last_fire = 0;
-                                        
-                                        entity.velocity.x -= direction.cos()*10.0;
-                                        entity.velocity.y -= direction.sin()*10.0;
                                     }
-                                    
-                                },
 
-                                _ => {
+                                    events.push(GameEvent::ShotFired { player: id, weapon: WeaponType::Sprayer, alt: true, position: entity.position, direction: *direction, velocity: entity.velocity });
+                                    weapon.heat = 0;
+                                    weapon.overheated = false;
+                                    weapon.last_alt_fire = 0;
+                                }
 
-                                },
-                            }
+                                if weapon.heat >= self.tuning.sprayer_max_heat {
+                                    weapon.overheated = true;
+                                }
+
+                                if input.fire && !weapon.overheated && weapon.last_fire > cooldown {
+                                    weapon.heat += self.tuning.sprayer_heat;
+                                    let shotdirection = *direction+PI*self.rng.gameplay.gen_range(-0.1, 0.1);
+                                    appendlist.push(Entity {
+                                        position: Vec2::new(entity.position.x+direction.exact_cos()*100.0, entity.position.y+direction.exact_sin()*100.0),
+                                        velocity: Vec2::new(entity.velocity.x+shotdirection.exact_cos()*90.0, entity.velocity.y+shotdirection.exact_sin()*90.0),
+                                        class: Class::Projectile(WeaponType::Shotgun, 0, id, false),
+                                    });
+
+                                    events.push(GameEvent::ShotFired { player: id, weapon: WeaponType::Sprayer, alt: false, position: entity.position, direction: *direction, velocity: entity.velocity });
+                                    weapon.last_fire = 0;
+                                    apply_recoil(&mut entity.velocity, *direction, self.tuning.recoil_for(weapon.class), self.tuning.recoil_cap);
+                                }
 
-                            let (mut x_change, mut y_change): (f32, f32) = (0.0, 0.0);
-                            if is_key_down(KeyCode::W) || is_key_down(KeyCode::Up) {
-                                y_change -= 1.0;
-                            } if is_key_down(KeyCode::S) || is_key_down(KeyCode::Down) {
-                                y_change += 1.0;
-                            } if is_key_down(KeyCode::A) || is_key_down(KeyCode::Left) {
-                                x_change -= 1.0;
-                            } if is_key_down(KeyCode::D) || is_key_down(KeyCode::Right) {
-                                x_change += 1.0;
-                            }
 
-                            if x_change.abs() + y_change.abs() == 2.0 {
-                                x_change /= ROOT_2;
-                                y_change /= ROOT_2;
                             }
 
-                            entity.velocity.x += x_change;
-                            entity.velocity.y += y_change;
-                        }
+                            WeaponType::Grenade => {
+                                if let Some(cook) = weapon.cook {
+                                    if cook+1 >= self.tuning.grenade_fuse {
+                                        //held too long, goes off in hand
+                                        appendlist.push(Entity {
+                                            position: entity.position,
+                                            velocity: Vec2::new(0.0, 0.0),
+                                            class: Class::Grenade { fuse: 0, height: 0.0, lift: 0.0, owner: id },
+                                        });
+                                        weapon.cook = None;
+                                        weapon.last_fire = 0;
+                                    } else {
+                                        weapon.cook = Some(cook+1);
+                                    }
+                                } else if input.fire_pressed && weapon.last_fire > cooldown {
+                                    weapon.cook = Some(0);
+                                } else if input.alt_fire_pressed && weapon.last_alt_fire > alt_cooldown {
+                                    //alt fire rolls one underhand along the floor
+                                    appendlist.push(Entity {
+                                        position: Vec2::new(entity.position.x+direction.exact_cos()*60.0, entity.position.y+direction.exact_sin()*60.0),
+                                        velocity: Vec2::new(entity.velocity.x+direction.exact_cos()*self.tuning.grenade_roll_speed, entity.velocity.y+direction.exact_sin()*self.tuning.grenade_roll_speed),
+                                        class: Class::Grenade { fuse: self.tuning.grenade_fuse, height: 0.0, lift: 0.0, owner: id },
+                                    });
+                                    events.push(GameEvent::ShotFired { player: id, weapon: WeaponType::Grenade, alt: true, position: entity.position, direction: *direction, velocity: entity.velocity });
+                                    weapon.last_alt_fire = 0;
+                                }
 
-                        weapon.last_fire += 1;
+                                if weapon.cook.is_some() && !input.fire {
+                                    let cook = weapon.cook.take().unwrap();
+                                    let charge = cook.min(self.tuning.grenade_full_charge) as f32/self.tuning.grenade_full_charge as f32;
+                                    let speed = self.tuning.grenade_min_throw+(self.tuning.grenade_max_throw-self.tuning.grenade_min_throw)*charge;
+                                    appendlist.push(Entity {
+                                        position: Vec2::new(entity.position.x+direction.exact_cos()*90.0, entity.position.y+direction.exact_sin()*90.0),
+                                        velocity: Vec2::new(entity.velocity.x+direction.exact_cos()*speed, entity.velocity.y+direction.exact_sin()*speed),
+                                        class: Class::Grenade { fuse: self.tuning.grenade_fuse-cook, height: 20.0, lift: 4.0+6.0*charge, owner: id },
+                                    });
 
-                        draw_texture_ex(self.assets[0], entity.position.x-30.0, entity.position.y-30.0,  BLUE, DrawTextureParams { rotation: *direction, ..Default::default() });
+                                    events.push(GameEvent::ShotFired { player: id, weapon: WeaponType::Grenade, alt: false, position: entity.position, direction: *direction, velocity: entity.velocity });
+                                    weapon.last_fire = 0;
 
-                        if *health < 0.0 && preventmultikill && self.player.game.is_none() {
-                            deletelist.push(count);
-                            preventmultikill = false; 
-                            self.player.game = Some(0);                 
-                                
-                            for rotation in 0..30 {
-                                let rotation = rotation as f32/15.0*PI;
-                                appendlist.push(Entity {
-                                    position: entity.position,
-                                    velocity: Vec2::new(rotation.cos()*15.0, rotation.sin()*15.0),
-                                    class: Class::Particle(RED, 20)
-                                });
-    
-                                appendlist.push(Entity {
-                                    position: entity.position,
-                                    velocity: Vec2::new(rotation.cos()*10.0, rotation.sin()*10.0),
-                                    class: Class::Gold(rotation),
-                                });
-                                        
-                            }
+                                    apply_recoil(&mut entity.velocity, *direction, self.tuning.recoil_for(weapon.class), self.tuning.recoil_cap);
+                                }
+
+                            },
                         }
-                        
-                    } else {
-                        draw_texture_ex(self.assets[0], entity.position.x-30.0, entity.position.y-30.0,  RED, DrawTextureParams { rotation: *direction, ..Default::default() });
-                        let text_center = get_text_center(&format!("{:?}", *health as i8), None, 30, 1.0, 0.0);
-                        draw_text(&format!("{:?}", *health as i8), entity.position.x-text_center.x, entity.position.y+50.0-text_center.y, 30.0, RED);
-                    
-                        if *health < 0.0 && preventmultikill {
-                            deletelist.push(count);
-                            preventmultikill = false;                  
-                                
-                            for rotation in 0..30 {
-                                let rotation = rotation as f32/15.0*PI;
-                                appendlist.push(Entity {
-                                    position: entity.position,
-                                    velocity: Vec2::new(rotation.cos()*15.0, rotation.sin()*15.0),
-                                    class: Class::Particle(RED, 20)
-                                });
-    
-                                appendlist.push(Entity {
-                                    position: entity.position,
-                                    velocity: Vec2::new(rotation.cos()*10.0, rotation.sin()*10.0),
-                                    class: Class::Gold(rotation),
-                                });
-                                        
-                            }
+
+                        let mut acceleration = self.tuning.acceleration*self.tuning.speed_for(weapon.class);
+                        if weapon.scoped > 0 {
+                            acceleration *= self.tuning.scoped_speed;
+                        }
+                        let max_speed = if *speed_boost > 0 {
+                            acceleration *= self.tuning.speed_boost;
+                            self.tuning.max_speed*self.tuning.speed_boost
+                        } else {
+                            self.tuning.max_speed
+                        };
+
+                        entity.velocity.x += input.movement.x*acceleration;
+                        entity.velocity.y += input.movement.y*acceleration;
+
+                        //soft speed cap, anything past it bleeds off fast instead of being cut dead
+                        let speed = entity.velocity.length();
+                        if speed > max_speed {
+                            entity.velocity *= (max_speed+(speed-max_speed)*self.tuning.overspeed_drag)/speed;
+                        }
+
+                        if input.dash && *last_dash > self.tuning.dash_cooldown {
+                            let heading = if input.movement.length() > 0.0 {input.movement.normalize()} else {Vec2::new(direction.exact_cos(), direction.exact_sin())};
+                            entity.velocity = heading;
+                            *last_dash = 0;
                         }
                     }
 
-                    for x in 0..MAP_SIZE_X {
-                        for y in 0..MAP_SIZE_Y {
-                            if self.map[x*MAP_SIZE_X+y].1 {
-                                let hitbox = &Rect::new(x as f32*50.0, y as f32*50.0, 50.0, 50.0);
-                                if check_box_hit(entity.position, entity.position+entity.velocity, hitbox)  {
-                                    let direction = if entity.position.x-hitbox.x > 0.0 {((entity.position.y-hitbox.y)/(entity.position.x-hitbox.x)).atan()} else {((entity.position.y-hitbox.y)/(entity.position.x-hitbox.x)).atan()+PI};
-                                    entity.velocity *= if direction < PI*-0.25 {
-                                        Vec2::new(0.0, 1.0)
-                                    } else if direction < PI*0.25 {
-                                        Vec2::new(1.0, 0.0)
-                                    } else if direction < PI*0.75 {
-                                        Vec2::new(0.0, -1.0)
-                                    } else if direction < PI*1.25 {
-                                        Vec2::new(1.0, 0.0)
-                                    } else {
-                                        Vec2::new(0.0, 1.0)
-                                    };
+                    //a dash holds a fixed speed for its duration so the distance always comes out the same
+                    if *last_dash < self.tuning.dash_ticks {
+                        entity.velocity = entity.velocity.normalize_or_zero()*self.tuning.dash_distance/self.tuning.dash_ticks as f32;
+                    }
+                    *last_dash = last_dash.saturating_add(1);
+                    *damage_boost = damage_boost.saturating_sub(1);
+                    *speed_boost = speed_boost.saturating_sub(1);
+
+                    weapon.last_fire += 1;
+                    weapon.last_alt_fire += 1;
+                    weapon.heat = weapon.heat.saturating_sub(1);
+                    if weapon.heat == 0 {
+                        weapon.overheated = false;
+                    }
 
-                                    entity.position.x += entity.velocity.x;
-                                    entity.position.y += entity.velocity.y;
-                                }
-                            }
+                    if *health < 0.0 {
+                        deletelist.push(count);
+                        events.push(GameEvent::Kill { victim: id, killer: *last_attacker, position: entity.position });
+
+                        for rotation in 0..30 {
+                            let rotation = rotation as f32/15.0*PI;
+                            appendlist.push(Entity {
+                                position: entity.position,
+                                velocity: Vec2::new(rotation.exact_cos()*10.0, rotation.exact_sin()*10.0),
+                                class: Class::Gold(rotation),
+                            });
                         }
                     }
 
+                    //walls stop whichever axis runs into them so players slide along instead of sticking
+                    if blocked(&self.map, Vec2::new(entity.position.x+entity.velocity.x, entity.position.y)) {
+                        entity.velocity.x = 0.0;
+                    }
+                    if blocked(&self.map, Vec2::new(entity.position.x, entity.position.y+entity.velocity.y)) {
+                        entity.velocity.y = 0.0;
+                    }
+                    if blocked(&self.map, entity.position+entity.velocity) {
+                        entity.velocity = Vec2::new(0.0, 0.0);
+                    }
+
                     if entity.position.x > MAP_SIZE_X as f32*50.0 || entity.position.y > MAP_SIZE_Y as f32*50.0 || entity.position.x < 0.0 || entity.position.y < 0.0 {
                         *health -= 1.0;
                     }
-                },       
+                },
 
                 Class::Gold(ref mut tick) => {
-                    draw_rectangle(entity.position.x, entity.position.y, 10.0, 10.0, Color::new(1.0, 0.84+tick.sin()*0.1, 0.0, 1.0));
                     *tick += 0.1;
 
-                    for hitbox in entities.iter() {
-                        if let Class::Player { weapon: _, direction: _, health: _ } = hitbox.class {
+                    //gold only follows and pays out to the closest player
+                    let mut nearest: Option<(usize, f32)> = None;
+                    for (index, hitbox) in entities.iter().enumerate() {
+                        if let Class::Player { .. } = hitbox.class {
                             let distance = hitbox.position.distance(entity.position);
-                            if distance < 100.0 {
-                                let direction = if entity.position.x-hitbox.position.x > 0.0 {
-                                    ((entity.position.y - hitbox.position.y)/(entity.position.x - hitbox.position.x)).atan()+PI
-                                } else {
-                                    ((entity.position.y - hitbox.position.y)/(entity.position.x - hitbox.position.x)).atan()
-                                };
-                                entity.velocity.x += direction.cos();
-                                entity.velocity.y += direction.sin();
-
-                                if distance < 40.0 {
-                                    self.player.gold += 1;
-                                    deletelist.push(count);
-                                }
+                            if distance < 100.0 && nearest.is_none_or(|(_, closest)| distance < closest) {
+                                nearest = Some((index, distance));
                             }
                         }
                     }
-                },
 
-                Class::Particle(ref mut color, ref mut fade) => {
-                    draw_rectangle(entity.position.x, entity.position.y, 10.0, 10.0, *color);
-                    *fade -= 1;
+                    if let Some((index, distance)) = nearest {
+                        let hitbox = &entities[index];
+                        let direction = if entity.position.x-hitbox.position.x > 0.0 {
+                            ((entity.position.y - hitbox.position.y)/(entity.position.x - hitbox.position.x)).exact_atan()+PI
+                        } else {
+                            ((entity.position.y - hitbox.position.y)/(entity.position.x - hitbox.position.x)).exact_atan()
+                        };
+                        entity.velocity.x += direction.exact_cos();
+                        entity.velocity.y += direction.exact_sin();
 
-                    if *fade < 30 {
-                        (*color).a = *fade as f32/30.0;
-                        if *fade == 0 {
+                        if distance < 40.0 {
+                            goldlist.push(index);
                             deletelist.push(count);
                         }
                     }
                 },
 
-                Class::Projectile(weapontype, ref mut tick, owner) => {
-                    match weapontype {
-                        WeaponType::Knife(_) => {                            
-                            draw_rectangle(entity.position.x-5.0, entity.position.y-5.0, 10.0, 10.0, Color::new(1.0-(*tick as f32)/20.0, 1.0-(*tick as f32)/20.0, 1.0-(*tick as f32)/20.0, 1.0));
-                            if *tick >= 15 {
-                                deletelist.push(count);
+                Class::Pickup { kind, ref mut respawn, ref mut claimant, ref mut claim } => {
+                    if *respawn > 0 {
+                        *respawn -= 1;
+                    } else {
+                        let mut standing = Vec::new();
+                        for (index, hitbox) in entities.iter().enumerate() {
+                            if let Class::Player { id, .. } = hitbox.class {
+                                if hitbox.position.distance(entity.position) < PICKUP_RADIUS {
+                                    standing.push((index, id));
+                                }
                             }
-                        },
+                        }
 
-                        WeaponType::Grenade => {
-                            draw_texture_ex(self.assets[8], entity.position.x-20.0, entity.position.y-20.0, WHITE, DrawTextureParams {rotation: *tick as f32/20.0, ..Default::default()});
-                            if *tick >= 80 {
-                                deletelist.push(count);
-                            for rotation in 0..30 {
-                                let rotation = rotation as f32/15.0*PI;
-                                appendlist.push(Entity {
-                                    position: entity.position,
-                                    velocity: Vec2::new(rotation.cos()*15.0, rotation.sin()*15.0),
-                                    class: Class::Particle(RED, 25)
-                                });
+                        //a pickup goes to whoever holds it alone for long enough, a second player on it freezes the claim
+                        match standing[..] {
+                            [] => {
+                                *claimant = None;
+                                *claim = 0;
+                            },
+                            [(index, id)] => {
+                                if *claimant != Some(id) {
+                                    *claimant = Some(id);
+                                    *claim = 0;
+                                }
+                                *claim += 1;
+                                if *claim >= self.tuning.pickup_claim {
+                                    pickuplist.push((index, kind));
+                                    *respawn = self.tuning.respawn_for(kind);
+                                    *claimant = None;
+                                    *claim = 0;
+                                }
+                            },
+                            _ => {},
+                        }
+                    }
+                },
 
-                                appendlist.push(Entity {
-                                    position: entity.position,
-                                    velocity: Vec2::new((rotation+0.1).cos()*17.0, (rotation+0.1).sin()*17.0),
-                                    class: Class::Particle(RED, 20)
-                                });
-                            }
-                            }
+                //cosmetic, these only ever live in effects
+                Class::Particle(..) | Class::AfterImage(..) => {},
+
+                Class::Grenade { ref mut fuse, ref mut height, ref mut lift, .. } => {
+                    //floor bounce
+                    *lift -= self.tuning.grenade_gravity;
+                    *height += *lift;
+                    if *height <= 0.0 {
+                        *height = 0.0;
+                        *lift = if *lift < -2.0 {-*lift*self.tuning.grenade_restitution} else {0.0};
+                    }
+
+                    //wall bounce
+                    let next = entity.position+entity.velocity;
+                    if solid_at(&self.map, Vec2::new(next.x, entity.position.y)) || next.x < 0.0 || next.x > MAP_SIZE_X as f32*50.0 {
+                        entity.velocity.x *= -self.tuning.grenade_restitution;
+                    }
+                    if solid_at(&self.map, Vec2::new(entity.position.x, next.y)) || next.y < 0.0 || next.y > MAP_SIZE_Y as f32*50.0 {
+                        entity.velocity.y *= -self.tuning.grenade_restitution;
+                    }
 
-                            
+                    if *fuse == 0 {
+                        deletelist.push(count);
+                        events.push(GameEvent::Explosion { position: entity.position });
+                    } else {
+                        *fuse -= 1;
+                    }
+                },
+
+                Class::Projectile(weapontype, ref mut tick, _, _) => {
+                    if !deletelist.contains(&count) && !line_of_sight(&self.map, entity.position, entity.position+entity.velocity) {
+                        deletelist.push(count);
+                        events.push(GameEvent::WallHit { position: ray_end(&self.map, entity.position, entity.velocity.y.exact_atan2(entity.velocity.x), entity.velocity.length()) });
+                    }
+                    match weapontype {
+                        WeaponType::Knife(_) => {
+                            if entity.velocity.length() < 5.0 && !deletelist.contains(&count) {
+                                deletelist.push(count);
+                            }
                         },
 
                         _ => {
-                            if !deletelist.contains(&count) {
-                                draw_line(entity.position.x, entity.position.y, entity.position.x+entity.velocity.x, entity.position.y+entity.velocity.y, 10.0, WHITE);
-                                if entity.velocity.distance(Vec2::new(0.0, 0.0)) < 5.0 {
-                                    deletelist.push(count)
-                                }
-                            }
-                            
-                            for i in 0..entity.velocity.length() as u32 {
-                                /*for hitbox in 0..entities.len() {
-                                    if entities[hitbox].position.distance(Vec2::new(entity.position.x + (i as f32).cos(), entity.position.y + (i as f32).cos())) < 40.0 {
-                                        //deal damage
-                                    }
-                                }*/
+                            if !deletelist.contains(&count) && entity.velocity.distance(Vec2::new(0.0, 0.0)) < 5.0 {
+                                deletelist.push(count)
                             }
                         },
 
@@ -631,143 +1214,574 @@ impl Game {
                 },
             }
 
-            
+
 
             entity.position.x += entity.velocity.x;
             entity.position.y += entity.velocity.y;
 
-            entity.velocity.x *= 0.90;
-            entity.velocity.y *= 0.90;
+            //grenades keep their speed in the air and roll to a stop on the floor
+            let drag = match entity.class {
+                Class::Grenade { height, .. } if height > 0.0 => 0.98,
+                Class::Player { .. } => self.tuning.friction,
+                _ => 0.90,
+            };
+            entity.velocity.x *= drag;
+            entity.velocity.y *= drag;
+        }
+
+        for (index, damage, knockback, attacker, weapon, source) in damagelist {
+            self.entities[index].velocity += knockback;
+            if let Class::Player { id, ref mut health, ref mut armor, ref mut last_attacker, .. } = self.entities[index].class {
+                hurt(health, armor, damage, self.tuning.armor_absorb);
+                *last_attacker = attacker;
+                events.push(GameEvent::Hit { target: id, attacker, weapon, damage, position: self.entities[index].position, source });
+            }
+        }
+
+        for index in goldlist {
+            if let Class::Player { id, ref mut inventory, .. } = self.entities[index].class {
+                inventory.gold += 1;
+                events.push(GameEvent::GoldCollected { player: id, total: inventory.gold });
+            }
+        }
+
+        for (index, kind) in pickuplist {
+            if let Class::Player { id, ref mut health, ref mut armor, ref mut damage_boost, ref mut speed_boost, ref mut inventory, .. } = self.entities[index].class {
+                inventory.pickups[kind as usize] += 1;
+                match kind {
+                    PickupKind::Health => *health = (*health+self.tuning.health_pack).min(100.0),
+                    PickupKind::Armor => *armor = (*armor+self.tuning.armor_pack).min(self.tuning.max_armor),
+                    PickupKind::Damage => *damage_boost = self.tuning.boost_ticks,
+                    PickupKind::Speed => *speed_boost = self.tuning.boost_ticks,
+                }
+                events.push(GameEvent::PickupTaken { player: id, kind, position: self.entities[index].position });
+            }
         }
 
         deletelist.sort();
         for (count, index) in deletelist.iter().enumerate() {
             self.entities.remove(index-count);
-            if index-count < self.player.index {
-                self.player.index -= 1;
-            }
         }
 
-        
+
 
         self.entities.append(&mut appendlist);
 
-        mouse_position = ((mouse_position_local().x+1.0)/2.0, (mouse_position_local().y+1.0)/2.0);
-        if sh > sw {
-            sw = sw/sh * 1600.0;
-            sh = 1600.0;
-            mouse_position.0 *= sw;
-            mouse_position.1 *= sh;
-        } else {
-            sh = sh/sw * 1600.0;
-            sw = 1600.0;
-            mouse_position.0 *= sw;
-            mouse_position.1 *= sh;
+        //once there's one player or nobody left the match is decided, nothing from here on changes who won
+        if !self.over && self.entities.iter().filter(|entity| matches!(entity.class, Class::Player { .. })).count() <= 1 {
+            self.over = true;
         }
+        self.scoreboard = self.entities.iter().filter_map(|entity| match entity.class {
+            Class::Player { id, inventory, paused, .. } => Some((id, inventory.gold, paused)),
+            _ => None,
+        }).collect();
+
+        self.tick += 1;
+        self.checksum = self.state(false).finish();
+
+        events
+    }
+
+    //everything the simulation carries from one tick to the next. effects, the camera and anything else only
+    //drawn are left out, they're allowed to differ between machines
+    fn state(&self, record: bool) -> StateHash {
+        let mut state = StateHash::new(record);
+        state.field(format_args!("tick"), self.tick as u64);
+        state.field(format_args!("over"), self.over as u64);
+        state.field(format_args!("next_id"), self.next_id as u64);
+        state.field(format_args!("rng"), self.rng.gameplay.state());
+        state.field(format_args!("entities"), self.entities.len() as u64);
+
+        //none hashes as all ones so it can't collide with id zero
+        let option = |value: Option<u32>| value.map_or(u64::MAX, |value| value as u64);
+        let weapon_type = |weapon: WeaponType| match weapon {
+            WeaponType::Knife(side) => 6+side as u64,
+            _ => weapon.slot_index() as u64,
+        };
+
+        for (index, entity) in self.entities.iter().enumerate() {
+            state.float(format_args!("entities[{}].position.x", index), entity.position.x);
+            state.float(format_args!("entities[{}].position.y", index), entity.position.y);
+            state.float(format_args!("entities[{}].velocity.x", index), entity.velocity.x);
+            state.float(format_args!("entities[{}].velocity.y", index), entity.velocity.y);
+
+            match entity.class {
+                Class::Player { id, weapon, direction, health, last_dash, armor, damage_boost, speed_boost, inventory, last_attacker, paused } => {
+                    state.field(format_args!("entities[{}].player.id", index), id as u64);
+                    state.float(format_args!("entities[{}].player.direction", index), direction);
+                    state.float(format_args!("entities[{}].player.health", index), health);
+                    state.float(format_args!("entities[{}].player.armor", index), armor);
+                    state.field(format_args!("entities[{}].player.last_dash", index), last_dash as u64);
+                    state.field(format_args!("entities[{}].player.damage_boost", index), damage_boost as u64);
+                    state.field(format_args!("entities[{}].player.speed_boost", index), speed_boost as u64);
+                    state.field(format_args!("entities[{}].player.gold", index), inventory.gold as u64);
+                    state.field(format_args!("entities[{}].player.last_attacker", index), option(last_attacker));
+                    state.field(format_args!("entities[{}].player.paused", index), paused as u64);
+                    state.field(format_args!("entities[{}].weapon.class", index), weapon_type(weapon.class));
+                    state.field(format_args!("entities[{}].weapon.last_fire", index), weapon.last_fire as u64);
+                    state.field(format_args!("entities[{}].weapon.last_alt_fire", index), weapon.last_alt_fire as u64);
+                    state.field(format_args!("entities[{}].weapon.cook", index), weapon.cook.map_or(u64::MAX, |cook| cook as u64));
+                    state.field(format_args!("entities[{}].weapon.scoped", index), weapon.scoped as u64);
+                    state.field(format_args!("entities[{}].weapon.parry", index), weapon.parry as u64);
+                    state.field(format_args!("entities[{}].weapon.burst", index), weapon.burst as u64);
+                    state.field(format_args!("entities[{}].weapon.heat", index), weapon.heat as u64);
+                    state.field(format_args!("entities[{}].weapon.overheated", index), weapon.overheated as u64);
+                },
+                Class::Gold(tick) => state.float(format_args!("entities[{}].gold", index), tick),
+                Class::Projectile(weapon, tick, owner, alt) => {
+                    state.field(format_args!("entities[{}].projectile.weapon", index), weapon_type(weapon));
+                    state.field(format_args!("entities[{}].projectile.tick", index), tick as u64);
+                    state.field(format_args!("entities[{}].projectile.owner", index), owner as u64);
+                    state.field(format_args!("entities[{}].projectile.alt", index), alt as u64);
+                },
+                Class::Grenade { fuse, height, lift, owner } => {
+                    state.field(format_args!("entities[{}].grenade.fuse", index), fuse as u64);
+                    state.field(format_args!("entities[{}].grenade.owner", index), owner as u64);
+                    state.float(format_args!("entities[{}].grenade.height", index), height);
+                    state.float(format_args!("entities[{}].grenade.lift", index), lift);
+                },
+                Class::Pickup { kind, respawn, claimant, claim } => {
+                    state.field(format_args!("entities[{}].pickup.kind", index), kind as u64);
+                    state.field(format_args!("entities[{}].pickup.respawn", index), respawn as u64);
+                    state.field(format_args!("entities[{}].pickup.claimant", index), option(claimant));
+                    state.field(format_args!("entities[{}].pickup.claim", index), claim as u64);
+                },
+                Class::Particle(..) | Class::AfterImage(..) => {},
+            }
+        }
+
+        state
+    }
+
+    //the renderer's side of the events, particles and after images never feed back into the simulation
+    fn spawn_effects(&mut self, events: &[GameEvent]) {
+        for event in events.iter() {
+            match *event {
+                GameEvent::ShotFired { player, weapon, alt, position, direction, velocity } => {
+                    let animation = self.animations.entry(player).or_insert_with(PlayerAnimation::new);
+                    animation.body.restart("fire");
+                    animation.weapon.restart("recoil");
+                    if !matches!(weapon, WeaponType::Knife(_)) {
+                        animation.flash = Some(Animator::new("muzzle_flash"));
+                    }
+
+                    match (weapon, alt) {
+                        (WeaponType::Knife(_), false) => {
+                            for rotation in -5..5 {
+                                self.effects.push(Entity {
+                                    position: Vec2::new(((rotation as f32)/30.0*PI+direction).cos()*80.0+position.x, ((rotation as f32)/30.0*PI+direction).sin()*80.0+position.y),
+                                    velocity: Vec2::new(((rotation as f32)/30.0*PI+direction).cos()*2.0+velocity.x, ((rotation as f32)/30.0*PI+direction).sin()*2.0+velocity.y),
+                                    class: Class::Particle(WHITE, 15),
+                                })
+                            }
+                        },
+
+                        (WeaponType::Knife(_), true) | (WeaponType::Grenade, true) => {},
+
+                        (WeaponType::Sprayer, true) => {
+                            for rotation in -4..5 {
+                                self.effects.push(Entity {
+                                    position: Vec2::new(position.x+direction.cos()*100.0, position.y+direction.sin()*100.0),
+                                    velocity: Vec2::new(((rotation as f32)/20.0*PI+direction).cos()*15.0+velocity.x, ((rotation as f32)/20.0*PI+direction).sin()*15.0+velocity.y),
+                                    class: Class::Particle(LIGHTGRAY, 25),
+                                })
+                            }
+                        },
+
+                        (WeaponType::Grenade, false) => {
+                            for rotation in -2..3 {
+                                self.effects.push(Entity {
+                                    position: Vec2::new(direction.cos()*110.0+position.x+((rotation as f32)/10.0*PI+direction).sin()*10.0, direction.sin()*110.0+position.y+((rotation as f32)/10.0*PI+direction).sin()*10.0),
+                                    velocity: Vec2::new(((rotation as f32)/10.0*PI+direction).cos()*5.0+velocity.x, ((rotation as f32)/10.0*PI+direction).sin()*5.0+velocity.y),
+                                    class: Class::Particle(GREEN, 15),
+                                })
+                            }
+                        },
+
+                        _ => {
+                            let (spread, distance) = match weapon {
+                                WeaponType::Sniper => (-5..6, 100.0),
+                                WeaponType::Sprayer => (-2..3, 110.0),
+                                _ => (-2..3, 100.0),
+                            };
+                            for rotation in spread {
+                                self.effects.push(Entity {
+                                    position: Vec2::new(direction.cos()*distance+position.x+((rotation as f32)/10.0*PI+direction).cos()*10.0, direction.sin()*distance+position.y+((rotation as f32)/10.0*PI+direction).cos()*10.0),
+                                    velocity: Vec2::new(((rotation as f32)/10.0*PI+direction).cos()*2.0+velocity.x, ((rotation as f32)/10.0*PI+direction).sin()*2.0+velocity.y),
+                                    class: Class::Particle(ORANGE, 15),
+                                })
+                            }
+                        },
+                    }
+                },
+
+                GameEvent::Hit { damage, position, .. } => {
+                    //a pellet draws a drop of blood, a knife a splash
+                    for _ in 0..(damage/5.0).ceil().clamp(1.0, 5.0) as u32 {
+                        let rotation = self.rng.cosmetic.gen_range(0.0, 2.0*PI);
+                        self.effects.push(Entity {
+                            position,
+                            velocity: Vec2::new(rotation.cos()*15.0, rotation.sin()*15.0),
+                            class: Class::Particle(RED, 20)
+                        });
+                    }
+                },
+
+                GameEvent::Kill { victim, position, .. } => {
+                    let direction = self.animations.remove(&victim).map_or(0.0, |animation| animation.direction);
+                    let color = if victim == self.player.id {self.gameplay.palette.ally()} else {self.gameplay.palette.enemy()};
+                    self.deaths.push((position, direction, color, Animator::new("death")));
+
+                    for rotation in 0..30 {
+                        let rotation = rotation as f32/15.0*PI;
+                        self.effects.push(Entity {
+                            position,
+                            velocity: Vec2::new(rotation.cos()*15.0, rotation.sin()*15.0),
+                            class: Class::Particle(RED, 20)
+                        });
+                    }
+                },
+
+                GameEvent::Explosion { position } => {
+                    for rotation in 0..30 {
+                        let rotation = rotation as f32/15.0*PI;
+                        self.effects.push(Entity {
+                            position,
+                            velocity: Vec2::new(rotation.cos()*15.0, rotation.sin()*15.0),
+                            class: Class::Particle(RED, 25)
+                        });
+
+                        self.effects.push(Entity {
+                            position,
+                            velocity: Vec2::new((rotation+0.1).cos()*17.0, (rotation+0.1).sin()*17.0),
+                            class: Class::Particle(RED, 20)
+                        });
+                    }
+                },
+
+                GameEvent::PickupTaken { kind, position, .. } => {
+                    for rotation in 0..12 {
+                        let rotation = rotation as f32/6.0*PI;
+                        self.effects.push(Entity {
+                            position,
+                            velocity: Vec2::new(rotation.cos()*8.0, rotation.sin()*8.0),
+                            class: Class::Particle(kind.color(), 20)
+                        });
+                    }
+                },
 
-        if scope {
-            sw *= 1.1;
-            sh *= 1.1;
-        }        
+                GameEvent::WallHit { position } => {
+                    for _ in 0..3 {
+                        let rotation = self.rng.cosmetic.gen_range(0.0, 2.0*PI);
+                        self.effects.push(Entity {
+                            position,
+                            velocity: Vec2::new(rotation.cos()*5.0, rotation.sin()*5.0),
+                            class: Class::Particle(LIGHTGRAY, 10)
+                        });
+                    }
+                },
+
+                GameEvent::WeaponSwitched { player, .. } => {
+                    let animation = self.animations.entry(player).or_insert_with(PlayerAnimation::new);
+                    animation.body.restart("swap");
+                    animation.weapon.restart("weapon_swap");
+                },
+
+                GameEvent::GoldCollected { .. } | GameEvent::Paused { .. } => {},
+            }
+        }
+    }
 
-        self.player.camera = Camera2D::from_display_rect(Rect { x: self.entities[self.player.index].position.x - sw/2.0, y: self.entities[self.player.index].position.y - sh/2.0, w: sw, h: sh, });
+    fn draw(&mut self) {
+        clear_background(BLACK);
         set_camera(&self.player.camera);
-        
+        draw_rectangle_lines(0.0, 0.0, MAP_SIZE_X as f32*50.0, MAP_SIZE_Y as f32*50.0, 20.0, RED);
 
-        for index in 1..7 {
-            draw_texture_ex(self.assets[index],  self.entities[self.player.index].position.x-370.0+(index as f32*100.0), self.entities[self.player.index].position.y+sh/2.0-65.0, WHITE, DrawTextureParams {rotation: -PI/4.0, dest_size: Some(Vec2::new(67.5, 30.0)),  ..Default::default()});
-            let selected =  if let Class::Player { ref mut weapon, direction: _, health: _ } = self.entities[self.player.index].class {
-                if is_mouse_button_pressed(MouseButton::Left) {
-                    if mouse_position.0 > sw/2.0-375.0+(index as f32*100.0) && mouse_position.0 < sw/2.0-295.0+(index as f32*100.0) && mouse_position.1 > sh-90.0 {
-                        (*weapon).class = match index {
-                            1 => WeaponType::Knife(true),
-                            2 => WeaponType::Gunner,
-                            3 => WeaponType::Grenade,
-                            4 => WeaponType::Shotgun,
-                            5 => WeaponType::Sprayer,
-                            6 => WeaponType::Sniper,
-                            _ => WeaponType::Knife(false),
-                        };
+        for x in 0..MAP_SIZE_X {
+            for y in 0..MAP_SIZE_Y {
+                if self.map[x*MAP_SIZE_X+y].1 {
+                    draw_rectangle(x as f32*50.0, y as f32*50.0, 50.0, 50.0, GRAY)
+                } else {
+                    draw_rectangle(x as f32*50.0, y as f32*50.0, 50.0, 50.0, Color::new(1.0, 1.0, 1.0, self.map[x*MAP_SIZE_X+y].0))
+                }
+            }
+        }
+
+        let mut scope = 0.0;
+        //animations run on real time, the match runs on ticks
+        let seconds = get_frame_time();
+        let visibility = &self.visibility;
+        let seen = |position: Vec2| visibility.as_ref().is_none_or(|visibility| visibility.contains(position));
+
+        for (count, entity) in self.entities.iter().enumerate() {
+            if let Some(visibility) = visibility {
+                if concealed(visibility, self.player.id, entity) {
+                    continue;
+                }
+            }
+
+            match entity.class {
+                Class::Player { id, weapon, direction, health, last_dash, damage_boost, speed_boost, .. } => {
+                    let state = if weapon.overheated {"reload"} else if entity.velocity.length() > 1.0 {"walk"} else {"idle"};
+                    let animation = self.animations.entry(id).or_insert_with(PlayerAnimation::new);
+                    animation.update(&self.sprites, state, seconds);
+                    animation.direction = direction;
+                    let animation = *animation;
+
+                    let (rotation, flip) = match weapon.class {
+                        WeaponType::Knife(side) => (direction+if side {1.0} else {-1.0}, side),
+                        _ => (direction, false),
+                    };
+                    self.sprites.draw(weapon_region(weapon.class), self.sprites.frame(&animation.weapon), entity.position.x+20.0, entity.position.y-20.0, WHITE, DrawTextureParams {rotation, pivot: Some(entity.position), flip_y: flip, ..Default::default()});
+
+                    //just past the end of the barrel
+                    if let Some(flash) = animation.flash {
+                        self.sprites.draw("muzzle_flash", self.sprites.frame(&flash), entity.position.x+100.0, entity.position.y-20.0, WHITE, DrawTextureParams {rotation: direction, pivot: Some(entity.position), ..Default::default()});
                     }
-                };
 
-                if match weapon.class { 
-                    WeaponType::Gunner => 2, 
-                    WeaponType::Sniper => 6,
-                    WeaponType::Shotgun => 4,
-                    WeaponType::Sprayer => 5,
-                    WeaponType::Grenade => 3,
-                    WeaponType::Knife(_) => 1, 
-                } == index {true} else {false}
-            } else {false};
-            draw_texture_ex(self.assets[7], self.entities[self.player.index].position.x-375.0+(index as f32*100.0), self.entities[self.player.index].position.y+sh/2.0-90.0, if selected {BLUE} else {WHITE}, DrawTextureParams {..Default::default()});
+                    //scoped snipers give themselves away with a laser
+                    if let WeaponType::Sniper = weapon.class {
+                        if weapon.scoped > 0 {
+                            let settle = weapon.scoped.min(SCOPE_SETTLE) as f32/SCOPE_SETTLE as f32;
+                            let muzzle = Vec2::new(entity.position.x+direction.cos()*90.0, entity.position.y+direction.sin()*90.0);
+                            let end = ray_end(&self.map, muzzle, direction, 3000.0);
+                            draw_line(muzzle.x, muzzle.y, end.x, end.y, 2.0+2.0*settle, Color::new(1.0, 0.0, 0.0, 0.2+0.5*settle));
+                        }
+                    }
+
+                    if weapon.parry > 0 {
+                        let guard = Vec2::new(entity.position.x+direction.cos()*55.0, entity.position.y+direction.sin()*55.0);
+                        let across = Vec2::new(-direction.sin(), direction.cos())*40.0;
+                        draw_line(guard.x-across.x, guard.y-across.y, guard.x+across.x, guard.y+across.y, 6.0, Color::new(1.0, 1.0, 1.0, weapon.parry as f32/PARRY_WINDOW as f32));
+                    }
+
+                    //last_dash has already ticked past the dash's first frame by the time it's drawn
+                    if last_dash <= self.tuning.dash_ticks {
+                        self.effects.push(Entity {
+                            position: entity.position,
+                            velocity: Vec2::new(0.0, 0.0),
+                            class: Class::AfterImage(if self.player.index == Some(count) {self.gameplay.palette.ally()} else {self.gameplay.palette.enemy()}, direction, 12),
+                        });
+                    }
+
+                    //boosted players glow so the other side knows to back off
+                    if damage_boost > 0 {
+                        draw_circle_lines(entity.position.x, entity.position.y, 45.0, 4.0, PickupKind::Damage.color());
+                    }
+                    if speed_boost > 0 {
+                        draw_circle_lines(entity.position.x, entity.position.y, 52.0, 4.0, PickupKind::Speed.color());
+                    }
+
+                    if self.player.index == Some(count) {
+                        scope = weapon.scoped.min(SCOPE_SETTLE) as f32/SCOPE_SETTLE as f32;
+
+                        self.sprites.draw("player", self.sprites.frame(&animation.body), entity.position.x-30.0, entity.position.y-30.0,  self.gameplay.palette.ally(), DrawTextureParams { rotation: direction, ..Default::default() });
+
+                        if let Some(cook) = weapon.cook {
+                            draw_fuse(entity.position-Vec2::new(0.0, 60.0), self.tuning.grenade_fuse-cook, self.tuning.grenade_fuse);
+                        } else if weapon.heat > 0 {
+                            draw_meter(entity.position-Vec2::new(0.0, 60.0), weapon.heat as f32/self.tuning.sprayer_max_heat as f32, if weapon.overheated {RED} else {ORANGE});
+                        }
+                    } else {
+                        self.sprites.draw("player", self.sprites.frame(&animation.body), entity.position.x-30.0, entity.position.y-30.0,  self.gameplay.palette.enemy(), DrawTextureParams { rotation: direction, ..Default::default() });
+                        let text_center = get_text_center(&format!("{:?}", health as i8), None, 30, 1.0, 0.0);
+                        draw_text(&format!("{:?}", health as i8), entity.position.x-text_center.x, entity.position.y+50.0-text_center.y, 30.0, RED);
+                    }
+                },
+
+                Class::Gold(tick) => {
+                    draw_rectangle(entity.position.x, entity.position.y, 10.0, 10.0, Color::new(1.0, 0.84+tick.sin()*0.1, 0.0, 1.0));
+                },
+
+                Class::Pickup { kind, respawn, claim, .. } => {
+                    if respawn > 0 {
+                        draw_circle_lines(entity.position.x, entity.position.y, 25.0, 2.0, Color::new(kind.color().r, kind.color().g, kind.color().b, 0.4));
+                        let text_center = get_text_center(&format!("{}", respawn/60+1), None, 30, 1.0, 0.0);
+                        draw_text(&format!("{}", respawn/60+1), entity.position.x-text_center.x, entity.position.y-text_center.y, 30.0, Color::new(1.0, 1.0, 1.0, 0.4));
+                    } else {
+                        draw_circle(entity.position.x, entity.position.y, 25.0+(get_time() as f32*4.0).sin()*3.0, kind.color());
+                        let text_center = get_text_center(kind.label(), None, 30, 1.0, 0.0);
+                        draw_text(kind.label(), entity.position.x-text_center.x, entity.position.y-text_center.y, 30.0, BLACK);
+
+                        let standing = self.entities.iter().filter(|other| matches!(other.class, Class::Player { .. }) && other.position.distance(entity.position) < PICKUP_RADIUS).count();
+                        if standing > 1 {
+                            let text_center = get_text_center("CONTESTED", None, 24, 1.0, 0.0);
+                            draw_text("CONTESTED", entity.position.x-text_center.x, entity.position.y-45.0-text_center.y, 24.0, WHITE);
+                        }
+
+                        if claim > 0 {
+                            draw_meter(entity.position+Vec2::new(0.0, 35.0), claim as f32/self.tuning.pickup_claim as f32, kind.color());
+                        }
+                    }
+                },
+
+                Class::Grenade { fuse, height, .. } => {
+                    draw_circle(entity.position.x, entity.position.y, 12.0, Color::new(0.0, 0.0, 0.0, 0.4));
+                    self.sprites.draw("grenade", None, entity.position.x-20.0, entity.position.y-20.0-height, WHITE, DrawTextureParams {rotation: (self.tuning.grenade_fuse-fuse) as f32/20.0, ..Default::default()});
+                    draw_fuse(entity.position+Vec2::new(0.0, 30.0), fuse, self.tuning.grenade_fuse);
+                },
+
+                Class::Projectile(weapontype, tick, _, _) => {
+                    match weapontype {
+                        WeaponType::Knife(side) => {
+                            self.sprites.draw("knife", None, entity.position.x-20.0, entity.position.y-20.0, WHITE, DrawTextureParams {rotation: tick as f32/2.0, flip_y: side, ..Default::default()});
+                        },
+
+                        _ => {
+                            draw_line(entity.position.x, entity.position.y, entity.position.x+entity.velocity.x, entity.position.y+entity.velocity.y, 10.0, WHITE);
+                        },
+                    }
+                },
+
+                Class::Particle(..) | Class::AfterImage(..) => {},
+            }
         }
 
-        draw_text(&format!("{:?}", self.player.gold), 10.0+self.entities[self.player.index].position.x-sw/2.0, 120.0+self.entities[self.player.index].position.y-sh/2.0, 80.0, YELLOW);            
-        draw_text(&format!("{:?}", if let Class::Player { weapon: _, direction: _, health } = self.entities[self.player.index].class {health as u8} else {0.0 as u8}), 10.0+self.entities[self.player.index].position.x-sw/2.0, 60.0+self.entities[self.player.index].position.y-sh/2.0, 80.0, RED);            
+        for (position, direction, color, animator) in self.deaths.iter_mut() {
+            animator.advance(seconds);
+            if !seen(*position) {
+                continue;
+            }
+            self.sprites.draw("player", self.sprites.frame(animator), position.x-30.0, position.y-30.0, *color, DrawTextureParams { rotation: *direction, ..Default::default() });
+        }
+        self.deaths.retain(|(_, _, _, animator)| !self.sprites.finished(animator));
+
+        for effect in self.effects.iter_mut() {
+            match effect.class {
+                Class::AfterImage(color, direction, ref mut fade) => {
+                    //a dash behind a wall would give away where it went
+                    if seen(effect.position) {
+                        self.sprites.draw("player", None, effect.position.x-30.0, effect.position.y-30.0, Color::new(color.r, color.g, color.b, *fade as f32/24.0), DrawTextureParams { rotation: direction, ..Default::default() });
+                    }
+                    *fade -= 1;
+                },
 
-        if self.player.game.is_some() {
-            draw_rectangle(self.entities[self.player.index].position.x-sw/2.0, self.entities[self.player.index].position.y-sh/2.0, sw, sh, Color::new(0.0, 0.0, 0.0, 0.5));
-            draw_texture(self.assets[9], self.entities[self.player.index].position.x-320.0, self.entities[self.player.index].position.y-160.0+(self.player.game.unwrap() as f32/20.0).cos()*10.0, WHITE);
+                Class::Particle(ref mut color, ref mut fade) => {
+                    draw_rectangle(effect.position.x, effect.position.y, 10.0, 10.0, *color);
+                    *fade -= 1;
 
-            if self.player.game.unwrap() > 400 {
-                return true;
+                    if *fade < 30 {
+                        color.a = *fade as f32/30.0;
+                    }
+                },
+
+                _ => {},
             }
 
-            self.player.game = Some(self.player.game.unwrap()+1);
+            effect.position += effect.velocity;
+            effect.velocity *= 0.90;
         }
+        self.effects.retain(|effect| !matches!(effect.class, Class::Particle(_, 0) | Class::AfterImage(_, _, 0)));
 
-        false
-    }
-}
+        if let Some(ref visibility) = self.visibility {
+            visibility.draw_fog(Color::new(0.0, 0.0, 0.0, 0.75));
+        }
+        self.feedback.draw_world();
 
-fn check_box_hit(line_start: Vec2, line_end: Vec2, rect: &Rect) -> bool {
-    let left = rect.x;
-    let right = rect.x + rect.w;
-    let top = rect.y;
-    let bottom = rect.y + rect.h;
+        let (sw, sh) = view_size();
 
-    let left_intersect = line_intersects_line(line_start, line_end, Vec2::new(left, top), Vec2::new(left, bottom));
-    let right_intersect = line_intersects_line(line_start, line_end, Vec2::new(right, top), Vec2::new(right, bottom));
-    let top_intersect = line_intersects_line(line_start, line_end, Vec2::new(left, top), Vec2::new(right, top));
-    let bottom_intersect = line_intersects_line(line_start, line_end, Vec2::new(left, bottom), Vec2::new(right, bottom));
+        //scoping zooms in and pushes the view out toward the cursor, the hud stays unzoomed
+        //the camera stays where we last stood once we're gone
+        if let Some(index) = self.player.index {
+            self.player.focus = self.entities[index].position;
+        }
+        let local = self.player.index.map(|index| self.entities[index].class);
 
-    left_intersect || right_intersect || top_intersect || bottom_intersect
-}
+        let view = self.player.focus+Vec2::new(mouse_position_local().x*sw/2.0, mouse_position_local().y*sh/2.0)*SCOPE_LOOK_AHEAD*scope+self.feedback.shake(&mut self.rng.cosmetic);
+        let zoom = 1.0-(1.0-SCOPE_ZOOM)*scope;
 
-fn line_intersects_line(a_start: Vec2, a_end: Vec2, b_start: Vec2, b_end: Vec2) -> bool {
-    let a_slope = (a_end.y - a_start.y) / (a_end.x - a_start.x);
-    let a_intercept = a_start.y - (a_slope * a_start.x);
-    let b_slope = (b_end.y - b_start.y) / (b_end.x - b_start.x);
-    let b_intercept = b_start.y - (b_slope * b_start.x);
+        self.player.camera = Camera2D::from_display_rect(Rect { x: view.x - sw*zoom/2.0, y: view.y - sh*zoom/2.0, w: sw*zoom, h: sh*zoom, });
 
-    if a_slope == b_slope {
-        return false;
-    }
+        //everything from here on is the hud, laid out on the screen rather than in the world
+        set_default_camera();
+        let layout = Layout::current();
+        let palette = self.gameplay.palette;
+
+        for index in 1..7 {
+            let slot = layout.weapon_slot(index);
+            let selected = if let Some(Class::Player { weapon, .. }) = local {
+                weapon.class.slot_index() == index
+            } else {false};
+            self.sprites.draw(weapon_region(WeaponType::slot(index)), None, slot.x+layout.size(5.0), slot.y+layout.size(25.0), WHITE, DrawTextureParams {rotation: -PI/4.0, dest_size: Some(Vec2::new(layout.size(67.5), layout.size(30.0))),  ..Default::default()});
+            draw_texture_ex(self.textures.get(TextureId::Slot), slot.x, slot.y, if selected {BLUE} else {WHITE}, DrawTextureParams {dest_size: Some(slot.size()), ..Default::default()});
+        }
 
-    let x = (b_intercept - a_intercept) / (a_slope - b_slope);
-    let y = (a_slope * x) + a_intercept;
+        layout.text(&format!("{:?}", if let Some(Class::Player { health, .. }) = local {health as u8} else {0.0 as u8}), Anchor::TopLeft, Vec2::new(10.0, 10.0), 80.0, RED);
+        if let Some(Class::Player { armor, last_dash, damage_boost, speed_boost, inventory, .. }) = local {
+            if armor > 0.0 {
+                layout.text(&format!("{:?}", armor as u8), Anchor::TopLeft, Vec2::new(150.0, 25.0), 50.0, PickupKind::Armor.color());
+            }
+            layout.text(&format!("{:?}", inventory.gold), Anchor::TopLeft, Vec2::new(10.0, 70.0), 80.0, YELLOW);
+
+            let ready = (last_dash as f32/self.tuning.dash_cooldown as f32).min(1.0);
+            let bar = layout.place(Anchor::TopLeft, Vec2::new(10.0, 140.0), Vec2::new(200.0, 16.0));
+            draw_rectangle(bar.x, bar.y, bar.w, bar.h, Color::new(0.0, 0.0, 0.0, 0.6));
+            draw_rectangle(bar.x, bar.y, bar.w*ready, bar.h, if ready < 1.0 {GRAY} else {SKYBLUE});
+
+            //active boosts count down in seconds under the dash bar
+            let mut row = 166.0;
+            for (kind, left) in [(PickupKind::Damage, damage_boost), (PickupKind::Speed, speed_boost)].iter() {
+                if *left > 0 {
+                    layout.text(&format!("{} {}", kind.name(), left/60+1), Anchor::TopLeft, Vec2::new(10.0, row), 40.0, kind.color());
+                    row += 40.0;
+                }
+            }
+        }
 
-    let a_within_bounds = x >= a_start.x.min(a_end.x) && x <= a_start.x.max(a_end.x) && y >= a_start.y.min(a_end.y) && y <= a_start.y.max(a_end.y);
-    let b_within_bounds = x >= b_start.x.min(b_end.x) && x <= b_start.x.max(b_end.x) && y >= b_start.y.min(b_end.y) && y <= b_start.y.max(b_end.y);
+        //everyone's gold in the top right, us in our own colour, with the kill feed under it
+        let mut row = 20.0;
+        for (id, gold, _) in self.scoreboard.iter() {
+            layout.text(&format!("{:?}", gold), Anchor::TopRight, Vec2::new(20.0, row), 40.0, if *id == self.player.id {palette.ally()} else {palette.enemy()});
+            row += 40.0;
+        }
+        self.kill_feed.draw(&layout, row+20.0, self.player.id, palette);
+        self.minimap.draw(&layout, &self.gameplay, &self.map, &self.entities, self.player.index, &self.visibility);
+
+        //anyone else sitting in a pause menu, unless they've gone altogether
+        let paused = self.scoreboard.iter().filter(|(id, _, paused)| *id != self.player.id && *paused).count();
+        if let Some(disconnect) = self.net.as_ref().and_then(|net| net.disconnect) {
+            let (text, color) = if disconnect == Disconnect::Left {("Opponent left", WHITE)} else {("Connection lost", RED)};
+            layout.text(text, Anchor::Top, Vec2::new(0.0, 40.0), 40.0, color);
+        } else if paused > 0 {
+            let text = if paused == 1 {"Opponent paused".to_string()} else {format!("{} players paused", paused)};
+            layout.text(&text, Anchor::Top, Vec2::new(0.0, 40.0), 40.0, WHITE);
+        }
 
-    a_within_bounds && b_within_bounds
+        self.gameplay.crosshair.draw(Vec2::from(mouse_position()));
+        self.feedback.draw_hud(&layout);
+
+        if let Some(game) = self.player.game {
+            draw_rectangle(0.0, 0.0, screen_width(), screen_height(), Color::new(0.0, 0.0, 0.0, 0.5));
+            let bob = Vec2::new(0.0, (game as f32/20.0).cos()*10.0);
+            if self.player.won {
+                layout.text("VICTORY", Anchor::Center, bob, 160.0, YELLOW);
+            } else {
+                let area = layout.place(Anchor::Center, bob, Vec2::new(640.0, 320.0));
+                draw_texture_ex(self.textures.get(TextureId::GameOver), area.x, area.y, WHITE, DrawTextureParams {dest_size: Some(area.size()), ..Default::default()});
+            }
+        }
+    }
 }
 
 struct Player {
-    gold: u32,
-    index: usize,
+    //none once we've died
+    index: Option<usize>,
+    id: u32,
+    focus: Vec2,
     camera: Camera2D,
     game: Option<u16>,
+    won: bool,
 }
 
 impl Player {
     fn new() -> Self {
         Self {
-            gold: 0,
-            index: 0,
+            index: Some(0),
+            id: 0,
+            focus: Vec2::new(0.0, 0.0),
             camera: Camera2D::from_display_rect(Rect::new(0.0, 0.0, 1.0, 1.0,)),
             game: None,
+            won: false,
         }
     }
 }
@@ -780,11 +1794,16 @@ struct Entity {
 }
 
 impl Entity {
-    fn player() -> Self {
+    fn player(map: &[(f32, bool); MAP_SIZE], id: u32, rng: &mut Stream) -> Self {
+        let mut position = Vec2::new(0.0, 0.0);
+        while position.x == 0.0 || blocked(map, position) {
+            position = Vec2::new(rng.gen_range(100.0, MAP_SIZE_X as f32*50.0-100.0), rng.gen_range(100.0, MAP_SIZE_Y as f32*50.0-100.0));
+        }
+
         Self {
-            position: Vec2::new(rand::gen_range(100.0, MAP_SIZE_X as f32*50.0-100.0), rand::gen_range(100.0, MAP_SIZE_Y as f32*50.0-100.0)),
+            position,
             velocity: Vec2::new(0.0, 0.0),
-            class: Class::Player { weapon: Weapon { class: {let gen = rand::gen_range(0.0, 6.0); if gen < 1.0 {WeaponType::Sniper} else if gen < 2.0 {WeaponType::Gunner} else if gen < 3.0 {WeaponType::Grenade} else if gen < 4.0 {WeaponType::Shotgun} else if gen < 5.0 {WeaponType::Sprayer} else {WeaponType::Knife(rand::gen_range(0.0, 2.0) < 1.0)}}, last_fire: 0 }, direction: rand::gen_range(-PI, PI), health: 100.0 },
+            class: Class::Player { id, weapon: Weapon::new({let gen = rng.gen_range(0.0, 6.0); if gen < 1.0 {WeaponType::Sniper} else if gen < 2.0 {WeaponType::Gunner} else if gen < 3.0 {WeaponType::Grenade} else if gen < 4.0 {WeaponType::Shotgun} else if gen < 5.0 {WeaponType::Sprayer} else {WeaponType::Knife(rng.gen_range(0.0, 2.0) < 1.0)}}), direction: rng.gen_range(-PI, PI), health: 100.0, last_dash: u32::MAX, armor: 0.0, damage_boost: 0, speed_boost: 0, inventory: Inventory::default(), last_attacker: None, paused: false },
         }
     }
 }
@@ -792,22 +1811,84 @@ impl Entity {
 #[derive(Clone, Copy)]
 enum Class {
     Player {
+        //stays the same for the whole match, unlike the entity's index
+        id: u32,
         weapon: Weapon,
         direction: f32,
         health: f32,
+        last_dash: u32,
+        armor: f32,
+        damage_boost: u32,
+        speed_boost: u32,
+        inventory: Inventory,
+        //who to credit if this player dies
+        last_attacker: Option<u32>,
+        //sitting in the pause menu, still in the match and still hittable
+        paused: bool,
     },
 
     Gold(f32),
 
     Particle(Color, u16),
 
-    Projectile(WeaponType, u16, Option<usize>),
+    AfterImage(Color, f32, u16),
+
+    //owner is the id of the player who fired it, and the flag is whether it came from alt fire
+    Projectile(WeaponType, u16, u32, bool),
+
+    //owner is the id of the player who threw it
+    Grenade {
+        fuse: u32,
+        height: f32,
+        lift: f32,
+        owner: u32,
+    },
+
+    //a fixed spawner, respawn counts down while it's taken
+    Pickup {
+        kind: PickupKind,
+        respawn: u32,
+        //the id of whoever's standing on it alone
+        claimant: Option<u32>,
+        claim: u32,
+    },
+}
+
+//what a player has picked up so far, kept on the player so it follows them rather than the screen
+#[derive(Clone, Copy, Default)]
+struct Inventory {
+    gold: u32,
+    //times each pickup kind was taken, in health, armor, damage, speed order
+    pickups: [u32; 4],
 }
 
 #[derive(Clone, Copy)]
 struct Weapon {
     class: WeaponType,
     last_fire: u32,
+    last_alt_fire: u32,
+    cook: Option<u32>,
+    scoped: u32,
+    parry: u32,
+    burst: u32,
+    heat: u32,
+    overheated: bool,
+}
+
+impl Weapon {
+    fn new(class: WeaponType) -> Self {
+        Self {
+            class,
+            last_fire: 0,
+            last_alt_fire: 0,
+            cook: None,
+            scoped: 0,
+            parry: 0,
+            burst: 0,
+            heat: 0,
+            overheated: false,
+        }
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -818,4 +1899,89 @@ enum WeaponType {
     Sprayer,
     Grenade, //
     Knife(bool), //
-}
\ No newline at end of file
+}
+
+impl WeaponType {
+    fn slot(index: usize) -> Self {
+        match index {
+            1 => WeaponType::Knife(true),
+            2 => WeaponType::Gunner,
+            3 => WeaponType::Grenade,
+            4 => WeaponType::Shotgun,
+            5 => WeaponType::Sprayer,
+            6 => WeaponType::Sniper,
+            _ => WeaponType::Knife(false),
+        }
+    }
+
+    fn slot_index(&self) -> usize {
+        match self {
+            WeaponType::Knife(_) => 1,
+            WeaponType::Gunner => 2,
+            WeaponType::Grenade => 3,
+            WeaponType::Shotgun => 4,
+            WeaponType::Sprayer => 5,
+            WeaponType::Sniper => 6,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            WeaponType::Knife(_) => "Knife",
+            WeaponType::Gunner => "Gunner",
+            WeaponType::Grenade => "Grenade",
+            WeaponType::Shotgun => "Shotgun",
+            WeaponType::Sprayer => "Sprayer",
+            WeaponType::Sniper => "Sniper",
+        }
+    }
+
+    //ticks between primary shots
+    fn cooldown(&self) -> u32 {
+        match self {
+            WeaponType::Knife(_) => 10,
+            WeaponType::Gunner => 10,
+            WeaponType::Grenade => 70,
+            WeaponType::Shotgun => 30,
+            WeaponType::Sprayer => 3,
+            WeaponType::Sniper => 30,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum PickupKind {
+    Health,
+    Armor,
+    Damage,
+    Speed,
+}
+
+impl PickupKind {
+    fn color(&self) -> Color {
+        match self {
+            PickupKind::Health => GREEN,
+            PickupKind::Armor => SKYBLUE,
+            PickupKind::Damage => ORANGE,
+            PickupKind::Speed => VIOLET,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            PickupKind::Health => "+",
+            PickupKind::Armor => "A",
+            PickupKind::Damage => "D",
+            PickupKind::Speed => "S",
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            PickupKind::Health => "HEALTH",
+            PickupKind::Armor => "ARMOR",
+            PickupKind::Damage => "DAMAGE",
+            PickupKind::Speed => "SPEED",
+        }
+    }
+}
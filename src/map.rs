@@ -0,0 +1,96 @@
+use crate::PickupKind;
+
+//a hand built arena. everything is given in tiles, walls as (x, y, width, height) and pickups on the corner
+//where four tiles meet, so one can sit dead in the middle of the map
+pub struct MapDefinition {
+    pub walls: &'static [(usize, usize, usize, usize)],
+    pub pickups: &'static [(usize, usize, PickupKind)],
+}
+
+//walls mirrored both ways and each pickup matched in the opposite corner, so neither spawn side gets a better
+//route to anything
+pub const ARENA: MapDefinition = MapDefinition {
+    walls: &[
+        //cover around the middle
+        (44, 35, 12, 2),
+        (44, 63, 12, 2),
+        (35, 44, 2, 12),
+        (63, 44, 2, 12),
+
+        //corner blocks
+        (25, 25, 6, 6),
+        (69, 25, 6, 6),
+        (25, 69, 6, 6),
+        (69, 69, 6, 6),
+
+        //long walls splitting the sides
+        (10, 48, 15, 4),
+        (75, 48, 15, 4),
+        (48, 10, 4, 15),
+        (48, 75, 4, 15),
+    ],
+    pickups: &[
+        //the damage boost sits in the open so it has to be fought over
+        (50, 50, PickupKind::Damage),
+        (15, 15, PickupKind::Health),
+        (85, 85, PickupKind::Health),
+        (85, 15, PickupKind::Armor),
+        (15, 85, PickupKind::Armor),
+        (50, 28, PickupKind::Speed),
+        (50, 72, PickupKind::Speed),
+    ],
+};
+
+//a grid of pillars and nothing else, every fight is a corner away from breaking line of sight
+pub const PILLARS: MapDefinition = MapDefinition {
+    walls: &[
+        (20, 20, 4, 4),
+        (38, 20, 4, 4),
+        (58, 20, 4, 4),
+        (76, 20, 4, 4),
+        (20, 38, 4, 4),
+        (38, 38, 4, 4),
+        (58, 38, 4, 4),
+        (76, 38, 4, 4),
+        (20, 58, 4, 4),
+        (38, 58, 4, 4),
+        (58, 58, 4, 4),
+        (76, 58, 4, 4),
+        (20, 76, 4, 4),
+        (38, 76, 4, 4),
+        (58, 76, 4, 4),
+        (76, 76, 4, 4),
+    ],
+    pickups: &[
+        (50, 50, PickupKind::Damage),
+        (10, 10, PickupKind::Health),
+        (90, 90, PickupKind::Health),
+        (90, 10, PickupKind::Armor),
+        (10, 90, PickupKind::Armor),
+        (29, 50, PickupKind::Speed),
+        (71, 50, PickupKind::Speed),
+    ],
+};
+
+//everything a lobby host can pick from, by name
+pub const MAPS: [(&str, MapDefinition); 2] = [("Arena", ARENA), ("Pillars", PILLARS)];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MAP_SIZE_X, MAP_SIZE_Y};
+
+    //nobody's side of any map has something the other side doesn't
+    #[test]
+    fn symmetric() {
+        let (width, height) = (MAP_SIZE_X, MAP_SIZE_Y);
+        for (name, map) in MAPS.iter() {
+            for (x, y, w, h) in map.walls.iter() {
+                assert!(map.walls.contains(&(width-x-w, *y, *w, *h)) && map.walls.contains(&(*x, height-y-h, *w, *h)), "{} wall at {}, {}", name, x, y);
+            }
+            for (x, y, kind) in map.pickups.iter() {
+                assert!(map.pickups.iter().any(|other| (other.0, other.1) == (width-x, height-y) && other.2 as usize == *kind as usize), "{} pickup at {}, {}", name, x, y);
+            }
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+use macroquad::prelude::*;
+
+use crate::hud::{Anchor, Layout};
+use crate::settings::Gameplay;
+use crate::visibility::Visibility;
+use crate::{Class, Entity, MAP_SIZE, MAP_SIZE_X, MAP_SIZE_Y};
+
+//how long an opponent's last known position hangs around after they drop out of sight
+const LAST_SEEN_TICKS: u16 = 300;
+//the arena is this many units across, the map shows all of it
+const ARENA_WIDTH: f32 = MAP_SIZE_X as f32*50.0;
+const ARENA_HEIGHT: f32 = MAP_SIZE_Y as f32*50.0;
+
+//a corner map of the arena. it's drawn into its own texture so a rotated map still stays inside its square
+pub struct Minimap {
+    //one pixel per tile, built the first time it's drawn since the match can run without a window
+    terrain: Option<Texture2D>,
+    target: Option<(RenderTarget, u32)>,
+    //opponents by id, where they were last seen and how long ago. zero means in sight right now
+    last_seen: HashMap<u32, (Vec2, u16)>,
+}
+
+impl Minimap {
+    pub fn new() -> Self {
+        Self {
+            terrain: None,
+            target: None,
+            last_seen: HashMap::new(),
+        }
+    }
+
+    //who the local player can see this tick, anyone else is remembered where they were last seen
+    pub fn see(&mut self, entities: &[Entity], local: Option<usize>, visibility: &Option<Visibility>) {
+        for (_, age) in self.last_seen.values_mut() {
+            *age = age.saturating_add(1);
+        }
+        self.last_seen.retain(|_, (_, age)| *age < LAST_SEEN_TICKS);
+
+        let visibility = match visibility {
+            Some(visibility) => visibility,
+            None => return,
+        };
+        for (index, entity) in entities.iter().enumerate() {
+            if let Class::Player { id, .. } = entity.class {
+                if Some(index) != local && visibility.contains(entity.position) {
+                    self.last_seen.insert(id, (entity.position, 0));
+                }
+            }
+        }
+    }
+
+    pub fn draw(&mut self, layout: &Layout, gameplay: &Gameplay, map: &[(f32, bool); MAP_SIZE], entities: &[Entity], local: Option<usize>, visibility: &Option<Visibility>) {
+        if gameplay.minimap_size <= 0.0 {
+            return;
+        }
+
+        let area = layout.place(Anchor::BottomRight, Vec2::new(20.0, 20.0), Vec2::new(gameplay.minimap_size, gameplay.minimap_size));
+        let pixels = area.w.max(1.0) as u32;
+        let terrain = *self.terrain.get_or_insert_with(|| terrain(map));
+        let target = match self.target {
+            Some((target, size)) if size == pixels => target,
+            _ => {
+                if let Some((target, _)) = self.target {
+                    target.delete();
+                }
+                let target = render_target(pixels, pixels);
+                self.target = Some((target, pixels));
+                target
+            },
+        };
+
+        //north up shows the whole arena, rotating turns it about the local player so their aim points up
+        let (center, rotation) = match (gameplay.minimap_rotate, local.map(|index| &entities[index])) {
+            (true, Some(Entity { position, class: Class::Player { direction, .. }, .. })) => (*position, (-PI/2.0-direction).to_degrees()),
+            _ => (Vec2::new(ARENA_WIDTH/2.0, ARENA_HEIGHT/2.0), 0.0),
+        };
+        //y isn't flipped like from_display_rect does, render targets come out upside down otherwise
+        set_camera(&Camera2D {
+            target: center,
+            zoom: Vec2::new(2.0/ARENA_WIDTH, 2.0/ARENA_HEIGHT),
+            rotation,
+            render_target: Some(target),
+            ..Default::default()
+        });
+        clear_background(Color::new(0.0, 0.0, 0.0, 0.0));
+        draw_texture_ex(terrain, 0.0, 0.0, WHITE, DrawTextureParams { dest_size: Some(Vec2::new(ARENA_WIDTH, ARENA_HEIGHT)), ..Default::default() });
+
+        //markers are sized in arena units, about the same on screen whatever size the map is
+        let unit = ARENA_WIDTH/gameplay.minimap_size;
+        //pickups out of sight are hidden like on the main view, nobody gets told one has come back
+        let seen = |position: Vec2| visibility.as_ref().is_none_or(|visibility| visibility.contains(position));
+        for entity in entities.iter().filter(|entity| seen(entity.position)) {
+            if let Class::Pickup { kind, respawn: 0, .. } = entity.class {
+                draw_circle(entity.position.x, entity.position.y, 4.0*unit, kind.color());
+            }
+        }
+
+        for (position, age) in self.last_seen.values() {
+            let enemy = gameplay.palette.enemy();
+            if *age == 0 {
+                draw_circle(position.x, position.y, 6.0*unit, enemy);
+            } else {
+                let fade = 1.0-*age as f32/LAST_SEEN_TICKS as f32;
+                draw_circle_lines(position.x, position.y, 6.0*unit, 2.0*unit, Color::new(enemy.r, enemy.g, enemy.b, fade));
+            }
+        }
+
+        if let Some(Entity { position, class: Class::Player { direction, .. }, .. }) = local.map(|index| &entities[index]) {
+            let point = |angle: f32, length: f32| *position+Vec2::new((direction+angle).cos(), (direction+angle).sin())*length*unit;
+            draw_triangle(point(0.0, 10.0), point(2.5, 7.0), point(-2.5, 7.0), gameplay.palette.ally());
+        }
+
+        set_default_camera();
+        let tint = Color::new(1.0, 1.0, 1.0, gameplay.minimap_opacity);
+        draw_rectangle(area.x, area.y, area.w, area.h, Color::new(0.0, 0.0, 0.0, 0.6*gameplay.minimap_opacity));
+        draw_texture_ex(target.texture, area.x, area.y, tint, DrawTextureParams { dest_size: Some(area.size()), ..Default::default() });
+        draw_rectangle_lines(area.x, area.y, area.w, area.h, layout.size(2.0), tint);
+    }
+}
+
+//walls light, floor in the same shades as the arena
+fn terrain(map: &[(f32, bool); MAP_SIZE]) -> Texture2D {
+    let mut image = Image::gen_image_color(MAP_SIZE_X as u16, MAP_SIZE_Y as u16, BLACK);
+    for x in 0..MAP_SIZE_X {
+        for y in 0..MAP_SIZE_Y {
+            let (shade, solid) = map[x*MAP_SIZE_X+y];
+            let color = if solid {LIGHTGRAY} else {Color::new(shade, shade, shade, 1.0)};
+            image.set_pixel(x as u32, y as u32, color);
+        }
+    }
+
+    let texture = Texture2D::from_image(&image);
+    texture.set_filter(FilterMode::Nearest);
+    texture
+}
@@ -0,0 +1,99 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use crate::rng::Stream;
+
+use super::Transport;
+
+//a bad connection to put between two local matches, for trying the netcode without a bad network to hand.
+//set from the command line and changed from the pause menu while a match is running
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct Conditions {
+    //milliseconds each way, and how far either side of that a packet can land
+    pub latency: f32,
+    pub jitter: f32,
+    //chances out of 1 per packet
+    pub loss: f32,
+    pub duplicate: f32,
+    //held back an extra round trip's worth, so it turns up behind packets sent after it
+    pub reorder: f32,
+}
+
+impl Conditions {
+    //  --latency 150 --jitter 20 --loss 5 --duplicate 1 --reorder 2    in milliseconds and percent
+    pub fn from_args(arguments: &[String]) -> Self {
+        let mut conditions = Self::default();
+        for pair in arguments.windows(2) {
+            let value = match pair[1].parse::<f32>() {
+                Ok(value) => value.max(0.0),
+                Err(_) => continue,
+            };
+            match pair[0].as_str() {
+                "--latency" => conditions.latency = value,
+                "--jitter" => conditions.jitter = value,
+                "--loss" => conditions.loss = (value/100.0).min(1.0),
+                "--duplicate" => conditions.duplicate = (value/100.0).min(1.0),
+                "--reorder" => conditions.reorder = (value/100.0).min(1.0),
+                _ => {},
+            }
+        }
+
+        conditions
+    }
+}
+
+//wraps a transport and makes its sends suffer. the conditions are shared so the debug menu can change them mid match
+pub struct Simulated<T: Transport> {
+    inner: T,
+    conditions: Rc<Cell<Conditions>>,
+    rng: Stream,
+    now: f64,
+    //packets waiting to go out and when they're due, in no particular order
+    queue: Vec<(f64, Vec<u8>)>,
+}
+
+impl<T: Transport> Simulated<T> {
+    pub fn new(inner: T, conditions: Rc<Cell<Conditions>>, seed: u64) -> Self {
+        Self {
+            inner,
+            conditions,
+            rng: Stream::new(seed, 0),
+            now: 0.0,
+            queue: Vec::new(),
+        }
+    }
+}
+
+impl<T: Transport> Transport for Simulated<T> {
+    fn send(&mut self, packet: &[u8]) {
+        let conditions = self.conditions.get();
+        if self.rng.gen_range(0.0, 1.0) < conditions.loss {
+            return;
+        }
+
+        let copies = if self.rng.gen_range(0.0, 1.0) < conditions.duplicate {2} else {1};
+        for _ in 0..copies {
+            let mut delay = conditions.latency+self.rng.gen_range(-conditions.jitter, conditions.jitter);
+            if self.rng.gen_range(0.0, 1.0) < conditions.reorder {
+                delay += conditions.latency*2.0+50.0;
+            }
+            self.queue.push((self.now+delay.max(0.0) as f64/1000.0, packet.to_vec()));
+        }
+    }
+
+    fn receive(&mut self) -> Option<Vec<u8>> {
+        self.inner.receive()
+    }
+
+    fn update(&mut self, now: f64) {
+        self.now = now;
+
+        //oldest due first, so jitter alone can shuffle them
+        self.queue.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        let due = self.queue.iter().take_while(|(time, _)| *time <= now).count();
+        for (_, packet) in self.queue.drain(..due) {
+            self.inner.send(&packet);
+        }
+        self.inner.update(now);
+    }
+}
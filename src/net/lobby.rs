@@ -0,0 +1,393 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::map::MAPS;
+use crate::tuning::Ruleset;
+use crate::Setup;
+
+use super::{Lockstep, Transport};
+
+//what goes over which channel, lobby chatter before the match and its packets after
+pub const LOBBY: i32 = 1;
+pub const MATCH: i32 = 2;
+
+//lobby messages. the host's word is final, the guest only ever says whether they're ready
+const STATE: u8 = 0;
+const READY: u8 = 1;
+const START: u8 = 2;
+
+pub enum Signal {
+    //our lobby exists and this is the code others join it with
+    Created(String),
+    Joined,
+    Failed(String),
+    //someone else in the lobby, by an id that stays the same while they're in it and a name to show
+    Arrived(String, String),
+    Left(String),
+    //on a channel, from someone by id
+    Data(i32, String, Vec<u8>),
+}
+
+//a server that puts players in the same lobby by code and passes messages between them
+pub trait Backend {
+    //the local player's name as everyone else sees it
+    fn name(&self) -> String;
+    //answered with created or failed
+    fn create(&mut self);
+    //answered with joined or failed
+    fn join(&mut self, code: &str);
+    //to everyone else in the lobby
+    fn send(&mut self, channel: i32, data: &[u8]);
+    fn poll(&mut self, now: f64) -> Option<Signal>;
+}
+
+//a match's packets through whatever server the lobby was on
+struct Relay {
+    backend: Rc<RefCell<dyn Backend>>,
+    now: f64,
+    //the only other player left the lobby
+    closed: bool,
+}
+
+impl Transport for Relay {
+    fn send(&mut self, packet: &[u8]) {
+        self.backend.borrow_mut().send(MATCH, packet);
+    }
+
+    //anything left over from the lobby is dropped
+    fn receive(&mut self) -> Option<Vec<u8>> {
+        loop {
+            match self.backend.borrow_mut().poll(self.now)? {
+                Signal::Data(MATCH, _, packet) => return Some(packet),
+                Signal::Left(_) => self.closed = true,
+                _ => {},
+            }
+        }
+    }
+
+    fn update(&mut self, now: f64) {
+        self.now = now;
+    }
+
+    fn closed(&self) -> bool {
+        self.closed
+    }
+}
+
+fn encode(setup: Setup) -> [u8; 2] {
+    [setup.map as u8, Ruleset::ALL.iter().position(|ruleset| *ruleset == setup.ruleset).unwrap_or(0) as u8]
+}
+
+fn decode(bytes: &[u8]) -> Option<Setup> {
+    match bytes {
+        [map, ruleset, ..] if (*map as usize) < MAPS.len() => Some(Setup {
+            map: *map as usize,
+            ruleset: *Ruleset::ALL.get(*ruleset as usize)?,
+        }),
+        _ => None,
+    }
+}
+
+//what a lobby hands over once the match starts: the setup, the seed, which player we are and the connection
+pub type Start = (Setup, u64, u32, Lockstep);
+
+//two players agreeing on a match before it starts. the host picks the map and rules, and once both are
+//ready it hands out the seed and everyone starts at once
+pub struct Lobby {
+    backend: Rc<RefCell<dyn Backend>>,
+    pub host: bool,
+    seed: u64,
+    pub code: Option<String>,
+    //the other player once they're in, by id and name
+    remote: Option<(String, String)>,
+    //everyone the server says is here, the guest works out which one's hosting from who sends the state
+    present: Vec<(String, String)>,
+    pub setup: Setup,
+    //the host's then the guest's
+    pub ready: [bool; 2],
+    pub error: Option<String>,
+}
+
+impl Lobby {
+    fn new(backend: Rc<RefCell<dyn Backend>>, host: bool, seed: u64) -> Self {
+        Self {
+            backend,
+            host,
+            seed,
+            code: None,
+            remote: None,
+            present: Vec::new(),
+            setup: Setup::default(),
+            ready: [false; 2],
+            error: None,
+        }
+    }
+
+    //a new lobby, its match will use this seed
+    pub fn create(backend: Rc<RefCell<dyn Backend>>, seed: u64) -> Self {
+        backend.borrow_mut().create();
+        Self::new(backend, true, seed)
+    }
+
+    pub fn join(backend: Rc<RefCell<dyn Backend>>, code: &str) -> Self {
+        backend.borrow_mut().join(code);
+        let mut lobby = Self::new(backend, false, 0);
+        lobby.code = Some(code.to_string());
+        lobby
+    }
+
+    //names and whether they're ready, the host first
+    pub fn players(&self) -> Vec<(String, bool)> {
+        let local = self.backend.borrow().name();
+        let remote = self.remote.as_ref().map(|(_, name)| name.clone());
+        let players = if self.host {[Some(local), remote]} else {[remote, Some(local)]};
+        players.iter().zip(self.ready.iter()).filter_map(|(name, ready)| name.clone().map(|name| (name, *ready))).collect()
+    }
+
+    //host only, whoever's joined has to ready up again for the new setup
+    pub fn choose(&mut self, setup: Setup) {
+        if self.host && setup != self.setup {
+            self.setup = setup;
+            self.ready[1] = false;
+            self.send_state();
+        }
+    }
+
+    pub fn toggle_ready(&mut self) {
+        if self.host {
+            self.ready[0] = !self.ready[0];
+            self.send_state();
+        } else if self.remote.is_some() {
+            self.ready[1] = !self.ready[1];
+            self.backend.borrow_mut().send(LOBBY, &[READY, self.ready[1] as u8]);
+        }
+    }
+
+    fn send_state(&mut self) {
+        let mut message = vec![STATE];
+        message.extend_from_slice(&encode(self.setup));
+        message.push(self.ready[0] as u8);
+        message.push(self.ready[1] as u8);
+        self.backend.borrow_mut().send(LOBBY, &message);
+    }
+
+    //the setup, seed, local player and connection once the match starts. the host's machine runs it and only
+    //sends the other player what they can see
+    fn start(&mut self) -> Start {
+        let (local, remote) = if self.host {(0, 1)} else {(1, 0)};
+        let relay = Relay {
+            backend: self.backend.clone(),
+            now: 0.0,
+            closed: false,
+        };
+        (self.setup, self.seed, local, Lockstep::hosted(Box::new(relay), local, remote))
+    }
+
+    //once a frame, and what to start the match with once it's time
+    pub fn update(&mut self, now: f64) -> Option<Start> {
+        loop {
+            let signal = match self.backend.borrow_mut().poll(now) {
+                Some(signal) => signal,
+                None => break,
+            };
+
+            match signal {
+                Signal::Created(code) => self.code = Some(code),
+                Signal::Joined => {},
+                Signal::Failed(message) => self.error = Some(message),
+                Signal::Arrived(id, name) => {
+                    //a lobby is for two, anyone after that is ignored
+                    if self.host && self.remote.is_none() {
+                        self.remote = Some((id.clone(), name.clone()));
+                        self.ready[1] = false;
+                        self.send_state();
+                    }
+                    self.present.push((id, name));
+                },
+                Signal::Left(id) => {
+                    self.present.retain(|(other, _)| *other != id);
+                    if self.remote.as_ref().is_some_and(|(remote, _)| *remote == id) {
+                        self.remote = None;
+                        self.ready[1] = false;
+                        if !self.host {
+                            self.error = Some("The host left".to_string());
+                        }
+                    }
+                },
+                Signal::Data(LOBBY, from, message) => {
+                    if self.host {
+                        if let ([READY, ready], Some((remote, _))) = (&message[..], &self.remote) {
+                            if *remote == from {
+                                self.ready[1] = *ready != 0;
+                                self.send_state();
+                            }
+                        }
+                        continue;
+                    }
+
+                    //the first to send the state is the host, nobody else would
+                    if self.remote.is_none() && message.first() == Some(&STATE) {
+                        self.remote = self.present.iter().find(|(id, _)| *id == from).cloned();
+                    }
+                    if self.remote.as_ref().is_none_or(|(remote, _)| *remote != from) {
+                        continue;
+                    }
+                    match (message.first(), decode(&message[1.min(message.len())..])) {
+                        (Some(&STATE), Some(setup)) if message.len() == 5 => {
+                            self.setup = setup;
+                            self.ready = [message[3] != 0, message[4] != 0];
+                        },
+                        (Some(&START), Some(setup)) if message.len() == 11 => {
+                            self.setup = setup;
+                            let mut seed = [0; 8];
+                            seed.copy_from_slice(&message[3..]);
+                            self.seed = u64::from_le_bytes(seed);
+                            //whatever's still queued after this belongs to the match
+                            return Some(self.start());
+                        },
+                        _ => {},
+                    }
+                },
+                Signal::Data(..) => {},
+            }
+        }
+
+        if self.host && self.remote.is_some() && self.ready == [true, true] {
+            let mut message = vec![START];
+            message.extend_from_slice(&encode(self.setup));
+            message.extend_from_slice(&self.seed.to_le_bytes());
+            self.backend.borrow_mut().send(LOBBY, &message);
+            return Some(self.start());
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use macroquad::prelude::*;
+
+    use super::super::standin::{Hub, StandIn};
+    use super::super::Disconnect;
+    use super::*;
+    use crate::input::Input;
+    use crate::Game;
+
+    //alice hosts and bob joins with her code, their stand in connections are kept for leaving
+    fn lobbies(hub: &Rc<RefCell<Hub>>) -> (Lobby, Lobby, Rc<RefCell<StandIn>>, Rc<RefCell<StandIn>>) {
+        let host_backend = Rc::new(RefCell::new(StandIn::new(hub, "alice")));
+        let mut host = Lobby::create(host_backend.clone(), 99);
+        host.update(0.0);
+
+        let guest_backend = Rc::new(RefCell::new(StandIn::new(hub, "bob")));
+        let guest = Lobby::join(guest_backend.clone(), host.code.as_ref().unwrap());
+        (host, guest, host_backend, guest_backend)
+    }
+
+    fn update(host: &mut Lobby, guest: &mut Lobby) -> (Option<Start>, Option<Start>) {
+        (host.update(0.0), guest.update(0.0))
+    }
+
+    #[test]
+    fn create_and_join_by_code() {
+        let hub = Hub::new();
+        let (mut host, mut guest, _, _) = lobbies(&hub);
+        assert_eq!(host.code.as_deref(), Some("LOBBY0"));
+        update(&mut host, &mut guest);
+        update(&mut host, &mut guest);
+
+        let both = vec![("alice".to_string(), false), ("bob".to_string(), false)];
+        assert_eq!(host.players(), both);
+        assert_eq!(guest.players(), both);
+
+        let mut nobody = Lobby::join(Rc::new(RefCell::new(StandIn::new(&hub, "carol"))), "WRONG");
+        nobody.update(0.0);
+        assert!(nobody.error.is_some());
+    }
+
+    #[test]
+    fn host_picks_and_both_ready_up() {
+        let hub = Hub::new();
+        let (mut host, mut guest, _, _) = lobbies(&hub);
+        update(&mut host, &mut guest);
+
+        guest.toggle_ready();
+        update(&mut host, &mut guest);
+        assert_eq!(host.ready, [false, true]);
+
+        //changing the setup unreadies the guest, and only the host can
+        let setup = Setup { map: 1, ruleset: Ruleset::Fast };
+        guest.choose(Setup::default());
+        host.choose(setup);
+        update(&mut host, &mut guest);
+        assert!(guest.setup == setup);
+        assert_eq!(guest.ready, [false, false]);
+
+        guest.toggle_ready();
+        host.toggle_ready();
+        let (hosted, joined) = match update(&mut host, &mut guest) {
+            (Some(hosted), Some(joined)) => (hosted, joined),
+            _ => panic!("didn't start"),
+        };
+        assert!(hosted.0 == setup && joined.0 == setup);
+        assert_eq!((hosted.1, hosted.2), (99, 0));
+        assert_eq!((joined.1, joined.2), (99, 1));
+
+        //and the match the host runs reaches the other side
+        let mut games = [Game::with_setup(hosted.1, hosted.0), Game::with_setup(joined.1, joined.0)];
+        games[0].connect(hosted.2, hosted.3);
+        games[1].connect(joined.2, joined.3);
+        for frame in 0..400 {
+            for (player, game) in games.iter_mut().enumerate() {
+                let turn = frame as f32/30.0+player as f32;
+                let input = Input { movement: Vec2::new(turn.cos(), turn.sin()), fire: frame%5 == 0, fire_pressed: frame%5 == 0, ..Input::idle(turn) };
+                game.advance(Some(input), false, frame as f64/60.0);
+            }
+        }
+        assert!(games[1].tick > 300 && games[0].tick-games[1].tick <= 4);
+        assert_eq!(games[1].scoreboard.len(), 2);
+        assert!(games[1].find_player(1).is_some());
+    }
+
+    //the server seeing someone go mid match ends it for the other side
+    #[test]
+    fn leaving_mid_match() {
+        let hub = Hub::new();
+        let (mut host, mut guest, _, guest_backend) = lobbies(&hub);
+        update(&mut host, &mut guest);
+        guest.toggle_ready();
+        host.toggle_ready();
+        let hosted = match update(&mut host, &mut guest) {
+            (Some(hosted), Some(_)) => hosted,
+            _ => panic!("didn't start"),
+        };
+
+        let mut game = Game::with_setup(hosted.1, hosted.0);
+        game.connect(hosted.2, hosted.3);
+        game.advance(Some(Input::idle(0.0)), false, 0.0);
+        guest_backend.borrow_mut().leave();
+        game.advance(Some(Input::idle(0.0)), false, 0.1);
+
+        assert_eq!(game.net.as_ref().unwrap().disconnect, Some(Disconnect::Left));
+        assert!(game.player.won);
+    }
+
+    #[test]
+    fn leaving() {
+        let hub = Hub::new();
+        let (mut host, mut guest, _, guest_backend) = lobbies(&hub);
+        update(&mut host, &mut guest);
+        guest_backend.borrow_mut().leave();
+        host.update(0.0);
+        assert_eq!(host.players().len(), 1);
+        assert!(host.error.is_none());
+
+        let (mut host, mut guest, host_backend, _) = lobbies(&hub);
+        update(&mut host, &mut guest);
+        host_backend.borrow_mut().leave();
+        guest.update(0.0);
+        assert_eq!(guest.players().len(), 1);
+        assert_eq!(guest.error.as_deref(), Some("The host left"));
+    }
+}
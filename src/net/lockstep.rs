@@ -0,0 +1,440 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::checksum;
+use crate::input::Input;
+
+use super::Transport;
+
+//ticks of input queued ahead before they're stepped, it hides that much round trip before anyone has to wait
+const INPUT_DELAY: u32 = 6;
+//inputs resent in one packet at most, anything older waits for the next
+const WINDOW: usize = 64;
+//local checksums kept around waiting for the other side's to compare against
+const CHECKSUM_HISTORY: u32 = 600;
+const NO_CHECKSUM: u32 = u32::MAX;
+const NO_DUMP: u32 = u32::MAX;
+//seconds without a packet from the other side before the match is given up on
+const STALL_TIMEOUT: f64 = 10.0;
+//nothing acknowledges leaving so it goes a few times over, and the stall timeout covers every copy going missing
+const LEAVE_COPIES: usize = 3;
+//a state dump goes over in pieces this big, a few of them a frame until the other side has the lot
+const DUMP_CHUNK: usize = 1024;
+const DUMP_CHUNKS_PER_POLL: usize = 4;
+
+//a hosted match's event history goes out this many ticks at most in one packet, anything newer waits for the next
+const EVENT_WINDOW: usize = 64;
+
+//what a packet is, its first byte
+const INPUTS: u8 = 0;
+const LEAVE: u8 = 1;
+const DUMP: u8 = 2;
+const SNAPSHOT: u8 = 3;
+
+//who runs the match. peers both step it from everyone's inputs. a hosted match is only stepped by the server,
+//which never sends its own inputs and hands the client what it can see of each tick instead, so a modified
+//client has nothing hidden to show
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Role {
+    Peer,
+    Server,
+    Client,
+}
+
+//why the other side isn't there any more
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Disconnect {
+    //they said so, or the transport saw them go
+    Left,
+    //nothing's arrived for STALL_TIMEOUT
+    TimedOut,
+}
+
+//two players stepping the same deterministic match. each side sends its inputs and only steps a tick once it has
+//everyone's for it. every packet carries all the inputs the other side hasn't acknowledged yet, so a lost one is
+//covered by the next, and the latest checksum so the two can tell when they've drifted apart. once they have, both
+//send over every field of one tick and write down where they differ
+pub struct Lockstep {
+    transport: Box<dyn Transport>,
+    pub role: Role,
+    local: u32,
+    pub remote: u32,
+    //the next tick to step, and the tick the next local input goes to
+    tick: u32,
+    next_input: u32,
+    //sent but not acknowledged, and received but not stepped yet
+    local_inputs: BTreeMap<u32, Input>,
+    remote_inputs: BTreeMap<u32, Input>,
+    //every remote input before this has arrived, it's what gets acknowledged. a client has no remote inputs
+    //and acknowledges the server's events the same way
+    received: u32,
+    checksums: BTreeMap<u32, u64>,
+    //the newest one, what gets sent. the ones above go once they've been compared
+    latest: (u32, u64),
+    //the other side's for ticks this side hasn't reached yet
+    remote_checksums: BTreeMap<u32, u64>,
+    //how many ticks have been checked against the other side, and the first that didn't match
+    pub verified: u32,
+    pub desync: Option<u32>,
+    //once they've drifted apart, the tick both sides write down every field at. the lower id picks it far enough
+    //ahead that neither has stepped it yet, and the other takes it from them
+    dump_at: Option<u32>,
+    //ours once it's recorded, with the pieces it's sent in, and as much of theirs as has arrived
+    local_dump: Option<Vec<(String, u64)>>,
+    dump_chunks: Vec<Vec<u8>>,
+    remote_dump: Vec<Option<Vec<u8>>>,
+    //they have all of ours
+    dump_delivered: bool,
+    next_chunk: usize,
+    //where the report comparing the two goes, and where it went
+    pub reports: PathBuf,
+    pub report: Option<PathBuf>,
+    //when a packet last arrived, none before the first poll
+    last_heard: Option<f64>,
+    pub disconnect: Option<Disconnect>,
+    //we've gone, nothing more gets sent
+    left: bool,
+    //serving, the newest state for the client and every tick's events it hasn't acknowledged yet. on the client,
+    //the newest state that hasn't been taken and each tick's events in order
+    state: Option<Vec<u8>>,
+    events: BTreeMap<u32, Vec<u8>>,
+}
+
+impl Lockstep {
+    pub fn new(transport: Box<dyn Transport>, local: u32, remote: u32) -> Self {
+        //the first few ticks are nobody's, both sides stand still through them
+        let idle: BTreeMap<u32, Input> = (0..INPUT_DELAY).map(|tick| (tick, Input::idle(0.0))).collect();
+
+        Self {
+            transport,
+            role: Role::Peer,
+            local,
+            remote,
+            tick: 0,
+            next_input: INPUT_DELAY,
+            local_inputs: idle.clone(),
+            remote_inputs: idle,
+            received: INPUT_DELAY,
+            checksums: BTreeMap::new(),
+            latest: (NO_CHECKSUM, 0),
+            remote_checksums: BTreeMap::new(),
+            verified: 0,
+            desync: None,
+            dump_at: None,
+            local_dump: None,
+            dump_chunks: Vec::new(),
+            remote_dump: Vec::new(),
+            dump_delivered: false,
+            next_chunk: 0,
+            reports: checksum::report_directory(),
+            report: None,
+            last_heard: None,
+            disconnect: None,
+            left: false,
+            state: None,
+            events: BTreeMap::new(),
+        }
+    }
+
+    //a match stepped by the lower id alone, the other side only sees what it's sent
+    pub fn hosted(transport: Box<dyn Transport>, local: u32, remote: u32) -> Self {
+        let mut lockstep = Self::new(transport, local, remote);
+        lockstep.role = if local < remote {Role::Server} else {Role::Client};
+        if lockstep.role == Role::Client {
+            //nothing to wait on from the server but its snapshots, and their events start from the first tick
+            lockstep.remote_inputs.clear();
+            lockstep.received = 0;
+        }
+        lockstep
+    }
+
+    //whether there's room for another local input, it can't run more than the delay ahead of the match. a client
+    //only hears about a tick once the server's stepped it, so it gets the round trip back as well
+    pub fn wants_input(&self) -> bool {
+        let delay = if self.role == Role::Client {INPUT_DELAY*2} else {INPUT_DELAY};
+        self.next_input <= self.tick+delay
+    }
+
+    pub fn push(&mut self, input: Input) {
+        self.local_inputs.insert(self.next_input, input);
+        self.next_input += 1;
+    }
+
+    //sends whatever the other side is missing and takes in whatever's arrived, once a frame
+    pub fn poll(&mut self, now: f64) {
+        //still updated after leaving, anything the transport's holding back has to get out
+        self.transport.update(now);
+        if self.left {
+            return;
+        }
+
+        let mut heard = *self.last_heard.get_or_insert(now);
+        while let Some(packet) = self.transport.receive() {
+            heard = now;
+            match packet.first() {
+                Some(&INPUTS) => self.read(&packet[1..]),
+                Some(&LEAVE) => {
+                    self.disconnect.get_or_insert(Disconnect::Left);
+                },
+                Some(&DUMP) => self.read_dump(&packet[1..]),
+                Some(&SNAPSHOT) if self.role == Role::Client => self.read_snapshot(&packet[1..]),
+                _ => {},
+            }
+        }
+        self.last_heard = Some(heard);
+        if self.transport.closed() {
+            self.disconnect.get_or_insert(Disconnect::Left);
+        } else if now-heard > STALL_TIMEOUT {
+            self.disconnect.get_or_insert(Disconnect::TimedOut);
+        }
+
+        let mut packet = vec![INPUTS];
+        packet.extend_from_slice(&self.received.to_le_bytes());
+        let first = self.local_inputs.keys().next().copied().unwrap_or(self.next_input);
+        packet.extend_from_slice(&first.to_le_bytes());
+        //a server's inputs would let the client play the whole match out for itself
+        let window = if self.role == Role::Server {0} else {WINDOW};
+        let inputs: Vec<&Input> = self.local_inputs.values().take(window).collect();
+        packet.push(inputs.len() as u8);
+        for input in inputs {
+            input.encode(&mut packet);
+        }
+        let (tick, checksum) = self.latest;
+        packet.extend_from_slice(&tick.to_le_bytes());
+        packet.extend_from_slice(&checksum.to_le_bytes());
+        packet.extend_from_slice(&self.dump_at.unwrap_or(NO_DUMP).to_le_bytes());
+        packet.push(self.remote_complete() as u8);
+
+        self.transport.send(&packet);
+
+        if let (Role::Server, Some(state)) = (self.role, &self.state) {
+            let mut packet = vec![SNAPSHOT];
+            packet.extend_from_slice(&(state.len() as u32).to_le_bytes());
+            packet.extend_from_slice(state);
+            let first = self.events.keys().next().copied().unwrap_or(0);
+            let ticks: Vec<&Vec<u8>> = self.events.values().take(EVENT_WINDOW).collect();
+            packet.extend_from_slice(&first.to_le_bytes());
+            packet.push(ticks.len() as u8);
+            for events in ticks {
+                packet.extend_from_slice(&(events.len() as u32).to_le_bytes());
+                packet.extend_from_slice(events);
+            }
+            self.transport.send(&packet);
+        }
+
+        let chunks = &self.dump_chunks;
+        if !chunks.is_empty() && !self.dump_delivered {
+            for _ in 0..DUMP_CHUNKS_PER_POLL.min(chunks.len()) {
+                let index = self.next_chunk%chunks.len();
+                let mut packet = vec![DUMP];
+                packet.extend_from_slice(&(index as u16).to_le_bytes());
+                packet.extend_from_slice(&(chunks.len() as u16).to_le_bytes());
+                packet.extend_from_slice(&chunks[index]);
+                self.transport.send(&packet);
+                self.next_chunk += 1;
+            }
+        }
+    }
+
+    //tells the other side we're gone, it's their match
+    pub fn leave(&mut self) {
+        if !self.left {
+            self.left = true;
+            for _ in 0..LEAVE_COPIES {
+                self.transport.send(&[LEAVE]);
+            }
+        }
+    }
+
+    //anything short or garbled is dropped, the next packet says it all again
+    fn read(&mut self, packet: &[u8]) {
+        let word = |at: usize| packet.get(at..at+4).map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+        let (acknowledged, first, count) = match (word(0), word(4), packet.get(8)) {
+            (Some(acknowledged), Some(first), Some(count)) => (acknowledged, first, *count as usize),
+            _ => return,
+        };
+        let end = 9+count*Input::SIZE;
+        let (tick, checksum, dump_at, delivered) = match (word(end), packet.get(end+4..end+12), word(end+12), packet.get(end+16)) {
+            (Some(tick), Some(bytes), Some(dump_at), Some(delivered)) => {
+                (tick, u64::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7]]), dump_at, *delivered != 0)
+            },
+            _ => return,
+        };
+        if dump_at != NO_DUMP && self.dump_at.is_none() && self.local > self.remote {
+            self.dump_at = Some(dump_at);
+        }
+        self.dump_delivered |= delivered;
+
+        for index in 0..count {
+            let at = 9+index*Input::SIZE;
+            let tick = first+index as u32;
+            if tick >= self.tick {
+                if let Some(input) = Input::decode(&packet[at..at+Input::SIZE]) {
+                    self.remote_inputs.entry(tick).or_insert(input);
+                }
+            }
+        }
+        while self.remote_inputs.contains_key(&self.received) {
+            self.received += 1;
+        }
+
+        //the other side has these, they don't need sending again. the one being stepped is kept for next()
+        self.local_inputs.retain(|tick, _| *tick >= acknowledged.min(self.tick));
+        if self.role == Role::Server {
+            self.events.retain(|tick, _| *tick >= acknowledged);
+        }
+
+        //only peers step the match on both sides, there's nothing to compare otherwise
+        if tick == NO_CHECKSUM || self.role != Role::Peer {
+            return;
+        }
+        match self.checksums.get(&tick) {
+            Some(local) => {
+                let local = *local;
+                self.compare(tick, local, checksum);
+                self.checksums.retain(|other, _| *other > tick);
+            },
+            None if tick >= self.tick => {
+                self.remote_checksums.insert(tick, checksum);
+            },
+            None => {},
+        }
+    }
+
+    fn compare(&mut self, tick: u32, local: u64, remote: u64) {
+        if local == remote {
+            self.verified += 1;
+        } else if self.desync.is_none() {
+            self.desync = Some(tick);
+            //the other side can't be past where our inputs reach
+            if self.local < self.remote {
+                self.dump_at = Some(self.tick+INPUT_DELAY+2);
+            }
+        }
+    }
+
+    //the state after the newest tick for a newer one and each tick's events in order, anything short or
+    //garbled is dropped like an input packet
+    fn read_snapshot(&mut self, packet: &[u8]) {
+        let word = |at: usize| packet.get(at..at+4).map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+        let length = match word(0) {
+            Some(length) => length as usize,
+            None => return,
+        };
+        let state = match packet.get(4..4+length) {
+            Some(state) => state,
+            None => return,
+        };
+        //the state leads with the tick it's from
+        if let Some(tick) = state.get(0..4).map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])) {
+            if tick >= self.tick {
+                self.tick = tick+1;
+                self.state = Some(state.to_vec());
+            }
+        }
+
+        let (first, count) = match (word(4+length), packet.get(8+length)) {
+            (Some(first), Some(count)) => (first, *count as u32),
+            _ => return,
+        };
+        let mut at = 9+length;
+        for tick in first..first+count {
+            let events = match word(at).and_then(|length| packet.get(at+4..at+4+length as usize)) {
+                Some(events) => events,
+                None => return,
+            };
+            at += 4+events.len();
+            //a gap means an older packet, everything before the gap is already here
+            if tick == self.received {
+                self.events.insert(tick, events.to_vec());
+                self.received += 1;
+            }
+        }
+    }
+
+    //serving, what the client's allowed to see after the tick just stepped
+    pub fn publish(&mut self, state: Vec<u8>, events: Vec<u8>) {
+        self.events.insert(self.tick-1, events);
+        self.state = Some(state);
+    }
+
+    //on the client, the newest state if it hasn't been taken yet
+    pub fn take_state(&mut self) -> Option<Vec<u8>> {
+        self.state.take()
+    }
+
+    //on the client, every tick's events that have arrived, oldest first
+    pub fn take_events(&mut self) -> Vec<Vec<u8>> {
+        std::mem::take(&mut self.events).into_values().collect()
+    }
+
+    //whether the match should hand over every field of the tick it just stepped
+    pub fn wants_dump(&self) -> bool {
+        self.dump_at == Some(self.tick.wrapping_sub(1)) && self.local_dump.is_none()
+    }
+
+    pub fn dump(&mut self, fields: Vec<(String, u64)>) {
+        let text: String = fields.iter().map(|(name, value)| format!("{} {}\n", name, value)).collect();
+        self.dump_chunks = text.as_bytes().chunks(DUMP_CHUNK).map(|chunk| chunk.to_vec()).collect();
+        self.local_dump = Some(fields);
+        self.write_report();
+    }
+
+    fn read_dump(&mut self, packet: &[u8]) {
+        let (index, count) = match (packet.get(0..2), packet.get(2..4)) {
+            (Some(index), Some(count)) => (u16::from_le_bytes([index[0], index[1]]) as usize, u16::from_le_bytes([count[0], count[1]]) as usize),
+            _ => return,
+        };
+        if self.remote_dump.is_empty() {
+            self.remote_dump = vec![None; count];
+        }
+        if let Some(chunk) = self.remote_dump.get_mut(index) {
+            chunk.get_or_insert_with(|| packet[4..].to_vec());
+        }
+        self.write_report();
+    }
+
+    fn remote_complete(&self) -> bool {
+        !self.remote_dump.is_empty() && self.remote_dump.iter().all(|chunk| chunk.is_some())
+    }
+
+    //once both sides' dumps are here, the first time only
+    fn write_report(&mut self) {
+        let (tick, local) = match (self.dump_at, &self.local_dump) {
+            (Some(tick), Some(local)) if self.report.is_none() && self.remote_complete() => (tick, local),
+            _ => return,
+        };
+
+        let bytes: Vec<u8> = self.remote_dump.iter().flatten().flatten().copied().collect();
+        let remote: Vec<(String, u64)> = String::from_utf8_lossy(&bytes).lines()
+            .filter_map(|line| line.rsplit_once(' ').and_then(|(name, value)| Some((name.to_string(), value.parse().ok()?))))
+            .collect();
+        match checksum::report(&self.reports, tick, self.local, local, &remote) {
+            Ok(path) => self.report = Some(path),
+            Err(error) => eprintln!("couldn't write the desync report: {}", error),
+        }
+    }
+
+    //everyone's input for the next tick once it's all here, ordered by player
+    pub fn next(&mut self) -> Option<Vec<(u32, Input)>> {
+        let local = *self.local_inputs.get(&self.tick)?;
+        let remote = self.remote_inputs.remove(&self.tick)?;
+        self.tick += 1;
+
+        let mut inputs = vec![(self.local, local), (self.remote, remote)];
+        inputs.sort_by_key(|(player, _)| *player);
+        Some(inputs)
+    }
+
+    //the state the match reached after the tick next() handed out
+    pub fn stepped(&mut self, checksum: u64) {
+        let tick = self.tick-1;
+        if let Some(remote) = self.remote_checksums.remove(&tick) {
+            self.compare(tick, checksum, remote);
+        }
+        self.checksums.insert(tick, checksum);
+        self.latest = (tick, checksum);
+        let oldest = self.tick.saturating_sub(CHECKSUM_HISTORY);
+        self.checksums.retain(|tick, _| *tick >= oldest);
+    }
+}
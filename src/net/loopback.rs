@@ -0,0 +1,57 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+use std::rc::Rc;
+
+use macroquad::prelude::*;
+
+use crate::input::Input;
+use crate::Game;
+
+use super::{Lockstep, Transport};
+
+//one end of a pair of queues, whatever one end sends the other receives
+pub struct Loopback {
+    outgoing: Rc<RefCell<VecDeque<Vec<u8>>>>,
+    incoming: Rc<RefCell<VecDeque<Vec<u8>>>>,
+}
+
+pub fn loopback() -> (Loopback, Loopback) {
+    let (a, b) = (Rc::new(RefCell::new(VecDeque::new())), Rc::new(RefCell::new(VecDeque::new())));
+    (Loopback { outgoing: a.clone(), incoming: b.clone() }, Loopback { outgoing: b, incoming: a })
+}
+
+impl Transport for Loopback {
+    fn send(&mut self, packet: &[u8]) {
+        self.outgoing.borrow_mut().push_back(packet.to_vec());
+    }
+
+    fn receive(&mut self) -> Option<Vec<u8>> {
+        self.incoming.borrow_mut().pop_front()
+    }
+}
+
+//the other machine in a network test, a second copy of the match run headless in the same process.
+//a bot walks it in circles so there's something to watch the lag on
+pub struct Peer {
+    game: Game,
+    ticks: u32,
+}
+
+impl Peer {
+    pub fn new(seed: u64, local: u32, net: Lockstep) -> Self {
+        let mut game = Game::new(seed);
+        game.connect(local, net);
+        Self {
+            game,
+            ticks: 0,
+        }
+    }
+
+    pub fn tick(&mut self, now: f64) {
+        self.ticks += 1;
+        let turn = self.ticks as f32/120.0;
+        let input = Input { movement: Vec2::new(turn.cos(), turn.sin()), ..Input::idle(turn+PI/2.0) };
+        self.game.advance(Some(input), false, now);
+    }
+}
@@ -0,0 +1,253 @@
+mod conditions;
+mod lobby;
+mod lockstep;
+mod loopback;
+mod nakama;
+#[cfg(test)]
+mod standin;
+mod udp;
+
+pub use conditions::{Conditions, Simulated};
+pub use lobby::{Backend, Lobby, Start};
+pub use lockstep::{Disconnect, Lockstep, Role};
+pub use loopback::{loopback, Peer};
+pub use nakama::{Nakama, Server};
+pub use udp::{Discovery, Host, Join, PORT};
+
+//how a match reaches the other side, the same for a lan game as for one through a server. packets can go missing, turn up twice or out of order,
+//whatever's on top has to cope
+pub trait Transport {
+    fn send(&mut self, packet: &[u8]);
+    //the next packet that's arrived, none once there aren't any
+    fn receive(&mut self) -> Option<Vec<u8>>;
+    //for transports with something to do over time, now is in seconds from any fixed point
+    fn update(&mut self, _now: f64) {}
+    //whether the other end is known to have gone, for transports that can tell
+    fn closed(&self) -> bool {
+        false
+    }
+}
+
+//two matches in one process over the simulated network, as far apart as a bad connection would put them
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use macroquad::prelude::*;
+
+    use super::*;
+    use crate::input::Input;
+    use crate::events::GameEvent;
+    use crate::{Class, Game, Weapon, WeaponType, MAP_SIZE_X};
+
+    const SEED: u64 = 11;
+
+    fn script(frame: u32, player: u32) -> Input {
+        let turn = frame as f32/50.0+player as f32*2.0;
+        Input {
+            movement: Vec2::new(turn.cos(), turn.sin()),
+            fire: frame%9 < 4,
+            fire_pressed: frame.is_multiple_of(9),
+            dash: frame%100 == 70,
+            ..Input::idle(turn*1.5)
+        }
+    }
+
+    fn connected(conditions: Conditions) -> [Game; 2] {
+        let conditions = Rc::new(Cell::new(conditions));
+        let (near, far) = loopback();
+        let mut games = [Game::new(SEED), Game::new(SEED)];
+        games[0].connect(0, Lockstep::new(Box::new(Simulated::new(near, conditions.clone(), 1)), 0, 1));
+        games[1].connect(1, Lockstep::new(Box::new(Simulated::new(far, conditions, 2)), 1, 0));
+        games
+    }
+
+    //runs both at 60 frames a second from this frame until they've each stepped this far, or gives up.
+    //returns the frame it got to
+    fn play(games: &mut [Game; 2], start: u32, ticks: u32) -> u32 {
+        for frame in start..ticks*4 {
+            if games.iter().all(|game| game.tick >= ticks) {
+                return frame;
+            }
+            for (player, game) in games.iter_mut().enumerate() {
+                game.advance(Some(script(frame, player as u32)), false, frame as f64/60.0);
+            }
+        }
+        panic!("stalled at ticks {} and {}", games[0].tick, games[1].tick);
+    }
+
+    fn assert_in_sync(games: &[Game; 2]) {
+        for game in games.iter() {
+            let net = game.net.as_ref().unwrap();
+            assert_eq!(net.desync, None);
+            assert!(net.verified > 200, "only {} ticks checked", net.verified);
+        }
+    }
+
+    //straight through with nothing in between, the two stay level and each side's newest checksum is always
+    //the one it's just compared
+    #[test]
+    fn perfect_connection() {
+        let (near, far) = loopback();
+        let mut games = [Game::new(SEED), Game::new(SEED)];
+        games[0].connect(0, Lockstep::new(Box::new(near), 0, 1));
+        games[1].connect(1, Lockstep::new(Box::new(far), 1, 0));
+        play(&mut games, 0, 600);
+        assert_in_sync(&games);
+    }
+
+    #[test]
+    fn laggy_and_lossy() {
+        let mut games = connected(Conditions { latency: 150.0, loss: 0.05, ..Conditions::default() });
+        play(&mut games, 0, 600);
+        assert_in_sync(&games);
+    }
+
+    #[test]
+    fn duplicated_and_reordered() {
+        let mut games = connected(Conditions { latency: 60.0, jitter: 40.0, loss: 0.1, duplicate: 0.2, reorder: 0.2 });
+        play(&mut games, 0, 600);
+        assert_in_sync(&games);
+    }
+
+    //walking away ends it for whoever's left, and they get the win
+    #[test]
+    fn leaving_forfeits() {
+        let mut games = connected(Conditions { latency: 80.0, ..Conditions::default() });
+        let frame = play(&mut games, 0, 120);
+        games[0].forfeit();
+        for frame in frame..frame+30 {
+            for (player, game) in games.iter_mut().enumerate() {
+                game.advance(Some(script(frame, player as u32)), false, frame as f64/60.0);
+            }
+        }
+
+        assert_eq!(games[1].net.as_ref().unwrap().disconnect, Some(Disconnect::Left));
+        assert!(games[1].player.game.is_some() && games[1].player.won);
+        assert!(games[0].player.game.is_some() && !games[0].player.won);
+    }
+
+    //a press on a frame that's too far ahead to queue an input still goes out with the next one
+    #[test]
+    fn presses_wait_for_room() {
+        let mut games = connected(Conditions::default());
+        for game in games.iter_mut() {
+            if let Class::Player { ref mut weapon, .. } = game.entities[0].class {
+                *weapon = Weapon::new(WeaponType::Sniper);
+                weapon.last_fire = 1000;
+            }
+        }
+
+        //the other side standing still leaves no room after the first few frames
+        for frame in 0..20 {
+            games[0].advance(Some(Input { fire_pressed: frame == 19, ..Input::idle(0.0) }), false, frame as f64/60.0);
+        }
+        let mut shots = 0;
+        for frame in 20..80 {
+            for game in games.iter_mut() {
+                let events = game.advance(Some(Input::idle(0.0)), false, frame as f64/60.0);
+                shots += events.iter().filter(|event| matches!(event, GameEvent::ShotFired { player: 0, .. })).count();
+            }
+        }
+        //once on each side
+        assert_eq!(shots, 2);
+    }
+
+    //someone who stops answering altogether is given up on, and nobody gets the win
+    #[test]
+    fn stall_timeout() {
+        let mut games = connected(Conditions::default());
+        let frame = play(&mut games, 0, 120);
+        let frozen = |seconds: u32, game: &mut Game| {
+            for frame in frame..frame+seconds*60 {
+                game.advance(Some(script(frame, 0)), false, frame as f64/60.0);
+            }
+        };
+
+        frozen(9, &mut games[0]);
+        assert_eq!(games[0].net.as_ref().unwrap().disconnect, None);
+        frozen(11, &mut games[0]);
+        assert_eq!(games[0].net.as_ref().unwrap().disconnect, Some(Disconnect::TimedOut));
+        assert!(games[0].player.game.is_some() && !games[0].player.won);
+    }
+
+    //and it does notice when they drift
+    #[test]
+    fn catches_a_desync() {
+        let mut games = connected(Conditions { latency: 50.0, ..Conditions::default() });
+        let frame = play(&mut games, 0, 120);
+        if let Class::Player { ref mut health, .. } = games[1].entities[0].class {
+            *health -= 1.0;
+        }
+        play(&mut games, frame, 240);
+        for game in games.iter() {
+            assert!(game.net.as_ref().unwrap().desync.is_some());
+        }
+    }
+
+    #[test]
+    fn writes_a_desync_report() {
+        let mut games = connected(Conditions { latency: 50.0, loss: 0.1, ..Conditions::default() });
+        let directory = std::env::temp_dir().join(format!("skill-duel-desyncs-{}", std::process::id()));
+        for game in games.iter_mut() {
+            game.net.as_mut().unwrap().reports = directory.clone();
+        }
+        let frame = play(&mut games, 0, 120);
+        if let Class::Player { ref mut health, .. } = games[1].entities[0].class {
+            *health -= 1.0;
+        }
+        play(&mut games, frame, 360);
+        for game in games.iter() {
+            let path = game.net.as_ref().unwrap().report.clone().expect("no report written");
+            assert_eq!(path.parent(), Some(directory.as_path()));
+            let text = std::fs::read_to_string(&path).unwrap();
+            let different = text.split("[local]").next().unwrap();
+            assert!(different.contains("entities[0].player.health"), "{}", text);
+        }
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    //a hosted match's client is never sent the server's player while there's a wall between them, not even
+    //their shots
+    #[test]
+    fn hosted_hides_what_the_client_cant_see() {
+        let conditions = Rc::new(Cell::new(Conditions { latency: 60.0, jitter: 20.0, loss: 0.1, duplicate: 0.1, reorder: 0.1 }));
+        let (near, far) = loopback();
+        let mut games = [Game::new(SEED), Game::new(SEED)];
+        games[0].connect(0, Lockstep::hosted(Box::new(Simulated::new(near, conditions.clone(), 1)), 0, 1));
+        games[1].connect(1, Lockstep::hosted(Box::new(Simulated::new(far, conditions, 2)), 1, 0));
+        for game in games.iter_mut() {
+            game.map[13*MAP_SIZE_X+20].1 = true;
+            for (id, position) in [(0, Vec2::new(750.0, 1025.0)), (1, Vec2::new(600.0, 1025.0))].iter() {
+                let index = game.find_player(*id).unwrap();
+                game.entities[index].position = *position;
+            }
+        }
+
+        let mut fired = 0;
+        let mut seen = 0;
+        for frame in 0..300 {
+            let events = games[0].advance(Some(Input { fire: true, fire_pressed: frame%20 == 0, ..Input::idle(0.0) }), false, frame as f64/60.0);
+            fired += events.iter().filter(|event| matches!(event, GameEvent::ShotFired { player: 0, .. })).count();
+            let events = games[1].advance(Some(Input::idle(0.0)), false, frame as f64/60.0);
+            seen += events.iter().filter(|event| matches!(event, GameEvent::ShotFired { player: 0, .. })).count();
+            //before the first state arrives it only has the spawns, which the seed gives away anyway
+            assert!(games[1].tick == 0 || games[1].find_player(0).is_none());
+        }
+        assert!(games[1].tick > 200 && games[1].find_player(1).is_some());
+        assert_eq!(games[1].scoreboard.len(), 2);
+        assert!(fired > 0);
+        assert_eq!(seen, 0);
+
+        //stepping out from behind the wall puts them in plain sight again
+        let index = games[0].find_player(1).unwrap();
+        games[0].entities[index].position = Vec2::new(800.0, 1200.0);
+        for frame in 300..400 {
+            for game in games.iter_mut() {
+                game.advance(Some(Input::idle(0.0)), false, frame as f64/60.0);
+            }
+        }
+        assert!(games[1].find_player(0).is_some());
+    }
+}
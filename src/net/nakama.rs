@@ -0,0 +1,221 @@
+use std::collections::VecDeque;
+use std::env;
+
+use nakama_rs::api_client::{ApiClient, Event};
+
+use super::lobby::{Backend, Signal};
+
+//seconds any one step can take before the server's given up on
+const TIMEOUT: f64 = 10.0;
+//socket messages read a frame at most, the client only takes one per tick
+const MESSAGES: usize = 32;
+
+//where the nakama server is, from the command line
+//  --nakama 127.0.0.1:7350 --nakama-key defaultkey
+pub struct Server {
+    pub address: String,
+    pub port: u32,
+    pub key: String,
+}
+
+impl Server {
+    pub fn from_args(arguments: &[String]) -> Self {
+        let mut server = Self {
+            address: "127.0.0.1".to_string(),
+            port: 7350,
+            key: "defaultkey".to_string(),
+        };
+        for pair in arguments.windows(2) {
+            match pair[0].as_str() {
+                "--nakama" => {
+                    let mut parts = pair[1].rsplitn(2, ':');
+                    match (parts.next().and_then(|port| port.parse().ok()), parts.next()) {
+                        (Some(port), Some(address)) => {
+                            server.address = address.to_string();
+                            server.port = port;
+                        },
+                        _ => server.address = pair[1].clone(),
+                    }
+                },
+                "--nakama-key" => server.key = pair[1].clone(),
+                _ => {},
+            }
+        }
+
+        server
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Step {
+    SigningIn,
+    SignedIn,
+    //making a relayed match, then asking the server for a code that points to it
+    Creating,
+    Registering,
+    //asking the server which match a code points to, then joining it
+    Finding,
+    Joining,
+    InLobby,
+}
+
+//a lobby on a nakama server. the match is relayed, the server only passes messages around, and the codes come from
+//the two rpcs in server/lobby.lua. every run signs up a throwaway account, there's nothing to keep between them
+pub struct Nakama {
+    client: ApiClient,
+    name: String,
+    step: Step,
+    //when the step started, set on the first poll after
+    since: Option<f64>,
+    //a create, or a join with its code, asked for before signing in finished
+    wanted: Option<Option<String>>,
+    signals: VecDeque<Signal>,
+}
+
+impl Nakama {
+    //random is only for the account, anything different each run will do
+    pub fn connect(server: &Server, random: u64) -> Self {
+        let name = format!("{}-{:04x}", env::var("USER").or_else(|_| env::var("USERNAME")).unwrap_or_else(|_| "player".to_string()), random&0xFFFF);
+        let mut client = ApiClient::new(&server.key, &server.address, server.port, "http");
+        client.register(&format!("{:016x}@skill-duel.invalid", random), &format!("{:016x}", random.rotate_left(32)), &name);
+
+        Self {
+            client,
+            name,
+            step: Step::SigningIn,
+            since: None,
+            wanted: None,
+            signals: VecDeque::new(),
+        }
+    }
+
+    fn set_step(&mut self, step: Step) {
+        self.step = step;
+        self.since = None;
+    }
+
+    fn fail(&mut self, message: &str) {
+        self.signals.push_back(Signal::Failed(message.to_string()));
+        self.wanted = None;
+        self.set_step(if self.client.authenticated() {Step::SignedIn} else {Step::SigningIn});
+    }
+
+    //rpc payloads go over as a json string, codes and match ids never need escaping
+    fn rpc(&mut self, name: &str, payload: &str) {
+        self.client.rpc(name, &format!("\"{}\"", payload));
+    }
+
+    fn events(&mut self) {
+        let session = self.client.session_id.clone();
+        while let Some(event) = self.client.try_recv() {
+            match event {
+                Event::Presence { joins, leaves } => {
+                    for presence in joins.into_iter().filter(|presence| Some(&presence.session_id) != session.as_ref()) {
+                        self.signals.push_back(Signal::Arrived(presence.session_id, presence.username));
+                    }
+                    for presence in leaves {
+                        self.signals.push_back(Signal::Left(presence.session_id));
+                    }
+                },
+                //sent as a length prefixed byte list
+                Event::MatchData { data, opcode, user_id } => {
+                    if data.len() >= 8 {
+                        self.signals.push_back(Signal::Data(opcode, user_id, data[8..].to_vec()));
+                    }
+                },
+            }
+        }
+    }
+}
+
+impl Backend for Nakama {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn create(&mut self) {
+        match self.step {
+            Step::SigningIn => self.wanted = Some(None),
+            Step::SignedIn => {
+                self.client.socket_create_match();
+                self.set_step(Step::Creating);
+            },
+            _ => {},
+        }
+    }
+
+    fn join(&mut self, code: &str) {
+        match self.step {
+            Step::SigningIn => self.wanted = Some(Some(code.to_string())),
+            Step::SignedIn => {
+                self.rpc("lobby_find", code);
+                self.set_step(Step::Finding);
+            },
+            _ => {},
+        }
+    }
+
+    fn send(&mut self, channel: i32, data: &[u8]) {
+        if self.step == Step::InLobby {
+            self.client.socket_send(channel, &data.to_vec());
+        }
+    }
+
+    fn poll(&mut self, now: f64) -> Option<Signal> {
+        //events come out of the client last first, so they're taken after every tick to keep them in order
+        for _ in 0..MESSAGES {
+            self.client.tick();
+            self.events();
+        }
+
+        let since = *self.since.get_or_insert(now);
+        let timed_out = now-since > TIMEOUT;
+        match self.step {
+            Step::SigningIn if self.client.authenticated() => {
+                self.set_step(Step::SignedIn);
+                match self.wanted.take() {
+                    Some(Some(code)) => self.join(&code),
+                    Some(None) => self.create(),
+                    None => {},
+                }
+            },
+            Step::SigningIn if timed_out && self.wanted.is_some() => self.fail("Couldn't reach the server"),
+            Step::Creating => match self.client.match_id() {
+                Some(id) => {
+                    self.rpc("lobby_register", &id);
+                    self.set_step(Step::Registering);
+                },
+                None if timed_out => self.fail("The server didn't make the lobby"),
+                None => {},
+            },
+            Step::Registering => match self.client.rpc_response() {
+                Some(code) => {
+                    self.signals.push_back(Signal::Created(code));
+                    self.set_step(Step::InLobby);
+                },
+                //the client doesn't pass errors on, a request that's finished without an answer failed
+                None if !self.client.in_progress() || timed_out => self.fail("The server didn't give out a code"),
+                None => {},
+            },
+            Step::Finding => match self.client.rpc_response() {
+                Some(id) => {
+                    self.client.socket_join_match_by_id(&id);
+                    self.set_step(Step::Joining);
+                },
+                None if !self.client.in_progress() || timed_out => self.fail("No lobby with that code"),
+                None => {},
+            },
+            Step::Joining => match self.client.match_id() {
+                Some(_) => {
+                    self.signals.push_back(Signal::Joined);
+                    self.set_step(Step::InLobby);
+                },
+                None if timed_out => self.fail("Couldn't join the lobby"),
+                None => {},
+            },
+            _ => {},
+        }
+
+        self.signals.pop_front()
+    }
+}
@@ -0,0 +1,104 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+
+use super::lobby::{Backend, Signal};
+
+//a lobby server in the same process that behaves like nakama does for a relayed match, for the tests.
+//everyone's signals wait in their own queue until they next poll
+#[derive(Default)]
+pub struct Hub {
+    names: Vec<String>,
+    inboxes: Vec<VecDeque<Signal>>,
+    lobbies: HashMap<String, Vec<usize>>,
+}
+
+impl Hub {
+    pub fn new() -> Rc<RefCell<Self>> {
+        Rc::new(RefCell::new(Self::default()))
+    }
+
+    fn others(&self, code: &str, member: usize) -> Vec<usize> {
+        self.lobbies.get(code).map_or_else(Vec::new, |members| members.iter().copied().filter(|other| *other != member).collect())
+    }
+}
+
+pub struct StandIn {
+    hub: Rc<RefCell<Hub>>,
+    member: usize,
+    lobby: Option<String>,
+}
+
+impl StandIn {
+    pub fn new(hub: &Rc<RefCell<Hub>>, name: &str) -> Self {
+        let member = {
+            let mut hub = hub.borrow_mut();
+            hub.names.push(name.to_string());
+            hub.inboxes.push(VecDeque::new());
+            hub.names.len()-1
+        };
+
+        Self {
+            hub: hub.clone(),
+            member,
+            lobby: None,
+        }
+    }
+
+    //leaving the way a closed socket would
+    pub fn leave(&mut self) {
+        if let Some(code) = self.lobby.take() {
+            let mut hub = self.hub.borrow_mut();
+            for other in hub.others(&code, self.member) {
+                hub.inboxes[other].push_back(Signal::Left(self.member.to_string()));
+            }
+            if let Some(members) = hub.lobbies.get_mut(&code) {
+                members.retain(|member| *member != self.member);
+            }
+        }
+    }
+}
+
+impl Backend for StandIn {
+    fn name(&self) -> String {
+        self.hub.borrow().names[self.member].clone()
+    }
+
+    fn create(&mut self) {
+        let mut hub = self.hub.borrow_mut();
+        let code = format!("LOBBY{}", hub.lobbies.len());
+        hub.lobbies.insert(code.clone(), vec![self.member]);
+        hub.inboxes[self.member].push_back(Signal::Created(code.clone()));
+        self.lobby = Some(code);
+    }
+
+    fn join(&mut self, code: &str) {
+        let mut hub = self.hub.borrow_mut();
+        if !hub.lobbies.contains_key(code) {
+            hub.inboxes[self.member].push_back(Signal::Failed("No lobby with that code".to_string()));
+            return;
+        }
+
+        hub.inboxes[self.member].push_back(Signal::Joined);
+        for other in hub.others(code, self.member) {
+            let (name, other_name) = (hub.names[self.member].clone(), hub.names[other].clone());
+            hub.inboxes[other].push_back(Signal::Arrived(self.member.to_string(), name));
+            hub.inboxes[self.member].push_back(Signal::Arrived(other.to_string(), other_name));
+        }
+        hub.lobbies.get_mut(code).unwrap().push(self.member);
+        self.lobby = Some(code.to_string());
+    }
+
+    fn send(&mut self, channel: i32, data: &[u8]) {
+        if let Some(ref code) = self.lobby {
+            let mut hub = self.hub.borrow_mut();
+            for other in hub.others(code, self.member) {
+                hub.inboxes[other].push_back(Signal::Data(channel, self.member.to_string(), data.to_vec()));
+            }
+        }
+    }
+
+    fn poll(&mut self, _now: f64) -> Option<Signal> {
+        self.hub.borrow_mut().inboxes[self.member].pop_front()
+    }
+}
@@ -0,0 +1,276 @@
+use std::env;
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+
+use super::Transport;
+
+//where a host waits for players, and where it listens for anyone on the lan looking for a game
+pub const PORT: u16 = 7777;
+const DISCOVERY_PORT: u16 = 7778;
+
+//every packet starts with this and what kind it is, anything else on the port is somebody else's
+const MAGIC: &[u8] = b"skill-duel";
+const HELLO: u8 = 0;
+const WELCOME: u8 = 1;
+const MATCH: u8 = 2;
+const FIND: u8 = 3;
+const ANNOUNCE: u8 = 4;
+
+//seconds between hellos while joining and broadcasts while looking, and how long a host stays listed without answering
+const HELLO_INTERVAL: f64 = 0.25;
+const FIND_INTERVAL: f64 = 1.0;
+const HOST_TIMEOUT: f64 = 3.5;
+
+fn packet(kind: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = MAGIC.to_vec();
+    packet.push(kind);
+    packet.extend_from_slice(body);
+    packet
+}
+
+//the kind and the rest, none if it isn't ours
+fn parse(packet: &[u8]) -> Option<(u8, &[u8])> {
+    if packet.len() > MAGIC.len() && packet.starts_with(MAGIC) {
+        Some((packet[MAGIC.len()], &packet[MAGIC.len()+1..]))
+    } else {
+        None
+    }
+}
+
+fn bind(port: u16) -> io::Result<UdpSocket> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, port))?;
+    socket.set_nonblocking(true)?;
+    Ok(socket)
+}
+
+//whatever's waiting on the socket, none once it's empty or it fails. a hosted match's snapshots run to a few
+//kilobytes with a lot going on, anything longer than the buffer would be cut short
+fn receive(socket: &UdpSocket) -> Option<(Vec<u8>, SocketAddr)> {
+    let mut buffer = [0; 16384];
+    loop {
+        match socket.recv_from(&mut buffer) {
+            Ok((length, from)) => return Some((buffer[..length].to_vec(), from)),
+            //windows reports the other end having gone away this way, it isn't fatal
+            Err(error) if error.kind() == io::ErrorKind::ConnectionReset => continue,
+            Err(_) => return None,
+        }
+    }
+}
+
+//a match straight to another machine over udp, no server in between
+pub struct Udp {
+    socket: UdpSocket,
+    peer: SocketAddr,
+    //the host's answer to a hello, sent again if the joiner didn't hear it the first time
+    welcome: Option<Vec<u8>>,
+}
+
+impl Transport for Udp {
+    fn send(&mut self, body: &[u8]) {
+        //a full buffer or an unreachable peer is a lost packet like any other
+        let _ = self.socket.send_to(&packet(MATCH, body), self.peer);
+    }
+
+    fn receive(&mut self) -> Option<Vec<u8>> {
+        while let Some((received, from)) = receive(&self.socket) {
+            if from != self.peer {
+                continue;
+            }
+            match parse(&received) {
+                Some((MATCH, body)) => return Some(body.to_vec()),
+                Some((HELLO, _)) => {
+                    if let Some(ref welcome) = self.welcome {
+                        let _ = self.socket.send_to(welcome, self.peer);
+                    }
+                },
+                _ => {},
+            }
+        }
+
+        None
+    }
+}
+
+//waits for one player to join, and answers anyone on the lan asking who's hosting
+pub struct Host {
+    socket: UdpSocket,
+    //none if something else on this machine already has the port, direct connects still work
+    discovery: Option<UdpSocket>,
+    name: String,
+    seed: u64,
+}
+
+impl Host {
+    pub fn new(port: u16, seed: u64) -> io::Result<Self> {
+        let name = env::var("HOSTNAME").or_else(|_| env::var("COMPUTERNAME")).or_else(|_| env::var("USER")).unwrap_or_else(|_| "Host".to_string());
+        Ok(Self {
+            socket: bind(port)?,
+            discovery: bind(DISCOVERY_PORT).ok(),
+            name,
+            seed,
+        })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.socket.local_addr().map_or(0, |address| address.port())
+    }
+
+    //the connection once someone says hello, it's their match from then on
+    pub fn poll(&mut self) -> Option<Udp> {
+        if let Some(ref discovery) = self.discovery {
+            while let Some((received, from)) = receive(discovery) {
+                if let Some((FIND, _)) = parse(&received) {
+                    let mut body = self.port().to_le_bytes().to_vec();
+                    body.extend_from_slice(self.name.as_bytes());
+                    let _ = discovery.send_to(&packet(ANNOUNCE, &body), from);
+                }
+            }
+        }
+
+        while let Some((received, from)) = receive(&self.socket) {
+            if let Some((HELLO, _)) = parse(&received) {
+                let welcome = packet(WELCOME, &self.seed.to_le_bytes());
+                let _ = self.socket.send_to(&welcome, from);
+                return Some(Udp {
+                    socket: self.socket.try_clone().ok()?,
+                    peer: from,
+                    welcome: Some(welcome),
+                });
+            }
+        }
+
+        None
+    }
+}
+
+//knocks on a host until it lets us in
+pub struct Join {
+    socket: UdpSocket,
+    host: SocketAddr,
+    last_hello: f64,
+}
+
+impl Join {
+    pub fn new(host: SocketAddr) -> io::Result<Self> {
+        Ok(Self {
+            socket: bind(0)?,
+            host,
+            last_hello: f64::NEG_INFINITY,
+        })
+    }
+
+    //the match's seed and the connection once the host answers
+    pub fn poll(&mut self, now: f64) -> Option<(u64, Udp)> {
+        if now-self.last_hello >= HELLO_INTERVAL {
+            self.last_hello = now;
+            let _ = self.socket.send_to(&packet(HELLO, &[]), self.host);
+        }
+
+        while let Some((received, from)) = receive(&self.socket) {
+            match parse(&received) {
+                Some((WELCOME, body)) if from == self.host && body.len() == 8 => {
+                    let mut seed = [0; 8];
+                    seed.copy_from_slice(body);
+                    return Some((u64::from_le_bytes(seed), Udp {
+                        socket: self.socket.try_clone().ok()?,
+                        peer: from,
+                        welcome: None,
+                    }));
+                },
+                _ => {},
+            }
+        }
+
+        None
+    }
+}
+
+//everyone hosting on the lan, found by broadcasting and listening for who answers
+pub struct Discovery {
+    socket: UdpSocket,
+    last_find: f64,
+    //the host's name, where to join it and when it last answered
+    pub hosts: Vec<(String, SocketAddr, f64)>,
+}
+
+impl Discovery {
+    pub fn new() -> io::Result<Self> {
+        let socket = bind(0)?;
+        socket.set_broadcast(true)?;
+        Ok(Self {
+            socket,
+            last_find: f64::NEG_INFINITY,
+            hosts: Vec::new(),
+        })
+    }
+
+    pub fn poll(&mut self, now: f64) {
+        if now-self.last_find >= FIND_INTERVAL {
+            self.last_find = now;
+            let _ = self.socket.send_to(&packet(FIND, &[]), (Ipv4Addr::BROADCAST, DISCOVERY_PORT));
+        }
+
+        while let Some((received, from)) = receive(&self.socket) {
+            if let Some((ANNOUNCE, body)) = parse(&received) {
+                if body.len() < 2 {
+                    continue;
+                }
+                let address = SocketAddr::new(from.ip(), u16::from_le_bytes([body[0], body[1]]));
+                let name = String::from_utf8_lossy(&body[2..]).to_string();
+                self.hosts.retain(|(_, other, _)| *other != address);
+                self.hosts.push((name, address, now));
+            }
+        }
+
+        self.hosts.retain(|(_, _, seen)| now-seen < HOST_TIMEOUT);
+        self.hosts.sort_by(|a, b| a.0.cmp(&b.0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time::Duration;
+
+    use super::*;
+
+    //a host and a joiner on this machine find each other and agree on the seed, then swap match packets
+    #[test]
+    fn host_and_join() {
+        let mut host = Host::new(0, 42).unwrap();
+        let mut join = Join::new(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), host.port())).unwrap();
+
+        let mut connected = (None, None);
+        for attempt in 0..200 {
+            if connected.0.is_none() {
+                connected.0 = host.poll();
+            }
+            if connected.1.is_none() {
+                connected.1 = join.poll(attempt as f64*0.01);
+            }
+            if connected.0.is_some() && connected.1.is_some() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let (mut host, (seed, mut join)) = match connected {
+            (Some(host), Some(join)) => (host, join),
+            _ => panic!("never connected"),
+        };
+        assert_eq!(seed, 42);
+
+        host.send(b"to the joiner");
+        join.send(b"to the host");
+        let mut received = (None, None);
+        for _ in 0..100 {
+            received.0 = received.0.or_else(|| host.receive());
+            received.1 = received.1.or_else(|| join.receive());
+            if received.0.is_some() && received.1.is_some() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(received.0.as_deref(), Some(&b"to the host"[..]));
+        assert_eq!(received.1.as_deref(), Some(&b"to the joiner"[..]));
+    }
+}
@@ -0,0 +1,54 @@
+//the match's own random numbers, so two machines or a replay given the same seed roll the same dice.
+//gameplay and cosmetics draw from separate streams, a particle more or less on one screen can't shift a shot on the other
+pub struct GameRng {
+    pub seed: u64,
+    //anything the simulation depends on, spread and spawns
+    pub gameplay: Stream,
+    //anything only drawn, particles and the floor
+    pub cosmetic: Stream,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            gameplay: Stream::new(seed, 1),
+            cosmetic: Stream::new(seed, 2),
+        }
+    }
+}
+
+//splitmix64, small and the same on every platform
+#[derive(Clone)]
+pub struct Stream {
+    state: u64,
+}
+
+impl Stream {
+    //each subsystem gets its own offset from the match seed so the streams don't overlap
+    pub fn new(seed: u64, stream: u64) -> Self {
+        Self {
+            state: seed^stream.wrapping_mul(0xD1B5_4A32_D192_ED03),
+        }
+    }
+
+    //where the stream is up to, it's part of the match state a desync check compares
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    pub fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z^(z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z^(z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z^(z >> 31)
+    }
+
+    //same arguments as macroquad's gen_range, low inclusive and high exclusive
+    pub fn gen_range(&mut self, low: f32, high: f32) -> f32 {
+        //the top 24 bits fill an f32's mantissa exactly
+        let unit = (self.next() >> 40) as f32/(1u64 << 24) as f32;
+        low+(high-low)*unit
+    }
+}
@@ -0,0 +1,52 @@
+use macroquad::prelude::*;
+
+use crate::audio::Cue;
+
+use super::widgets::{back_pressed, draw_heading, Action, Menu};
+use super::{Context, Scene, Transition};
+
+//asked before closing the game from the window or the pause menu
+pub struct ConfirmQuit {
+    menu: Menu,
+}
+
+impl ConfirmQuit {
+    pub fn new() -> Self {
+        Self {
+            menu: Menu::new(&["Keep playing", "Quit to desktop"]),
+        }
+    }
+}
+
+impl Scene for ConfirmQuit {
+    fn tick(&mut self, context: &mut Context) -> Transition {
+        let top = screen_height()/2.0;
+
+        set_default_camera();
+        clear_background(BLACK);
+        draw_heading("QUIT?", screen_height()/3.0);
+
+        let action = self.menu.update(top);
+        self.menu.draw(top);
+
+        if back_pressed() {
+            return Transition::Pop;
+        }
+
+        match action {
+            Some(Action::Moved) => context.audio.play_ui(Cue::Switch),
+            Some(Action::Choose(index)) => {
+                context.audio.play_ui(Cue::Click);
+                return if index == 0 {Transition::Pop} else {Transition::Quit};
+            },
+            _ => {},
+        }
+
+        Transition::None
+    }
+
+    //closing the window a second time means it
+    fn quit_requested(&mut self) -> Transition {
+        Transition::Quit
+    }
+}
@@ -0,0 +1,179 @@
+use macroquad::prelude::*;
+
+use crate::audio::Cue;
+use crate::net::{loopback, Lockstep, Peer, Simulated, Start};
+use crate::settings::Crosshair;
+use crate::{Game, Mode, Setup};
+
+use super::confirm::ConfirmQuit;
+use super::main_menu::MainMenu;
+use super::network::NetworkMenu;
+use super::results::Results;
+use super::settings_menu::SettingsMenu;
+use super::widgets::{back_pressed, draw_heading, Action, Menu};
+use super::{Context, Scene, Transition};
+
+const PAUSE_ITEMS: [&str; 5] = ["Resume", "Settings", "Forfeit", "Quit to menu", "Quit to desktop"];
+const NETWORK_ITEM: &str = "Network conditions";
+
+//a match in progress, the game is built when the scene goes on the stack so it gets the current settings
+pub struct InGame {
+    mode: Mode,
+    game: Option<Game>,
+    //the pause menu when it's open, drawn over the match
+    pause: Option<Menu>,
+    //the other side of a network test, stepped alongside this one every frame
+    peer: Option<Peer>,
+    //what was agreed, the seed, the local player and the connection for a match against another machine,
+    //taken once it starts
+    online: Option<Start>,
+}
+
+impl InGame {
+    pub fn new(mode: Mode) -> Self {
+        Self {
+            mode,
+            game: None,
+            pause: None,
+            peer: None,
+            online: None,
+        }
+    }
+
+    //a duel against whoever's on the other end, both sides have to agree on the setup and seed
+    pub fn online(setup: Setup, seed: u64, local: u32, net: Lockstep) -> Self {
+        Self {
+            online: Some((setup, seed, local, net)),
+            ..Self::new(Mode::Duel)
+        }
+    }
+
+    fn results(&self, game: &Game) -> Transition {
+        Transition::Replace(Box::new(Results::new(self.mode, game.player.won, game.player.id, game.stats.clone(), game.rng.seed, game.networked() && self.peer.is_none())))
+    }
+
+    fn set_paused(&mut self, context: &Context, paused: bool) {
+        self.pause = if paused {
+            let mut menu = Menu::new(&PAUSE_ITEMS);
+            if self.peer.is_some() {
+                menu.items.insert(2, NETWORK_ITEM.to_string());
+            }
+            Some(menu)
+        } else {
+            None
+        };
+        show_mouse(paused || context.settings.gameplay.crosshair == Crosshair::System);
+    }
+}
+
+impl Scene for InGame {
+    fn enter(&mut self, context: &mut Context) {
+        let (setup, seed) = self.online.as_ref().map_or_else(|| (Setup::default(), Game::clock_seed()), |(setup, seed, _, _)| (*setup, *seed));
+        let mut game = Game::init(&context.settings, &context.assets, self.mode, setup, seed);
+
+        if let Some((_, _, local, net)) = self.online.take() {
+            game.connect(local, net);
+        } else if context.net_test && self.mode == Mode::Duel {
+            //a duel against a copy of itself through the simulated network, the copy plays the other player
+            let (near, far) = loopback();
+            game.connect(0, Lockstep::new(Box::new(Simulated::new(near, context.conditions.clone(), seed)), 0, 1));
+            self.peer = Some(Peer::new(seed, 1, Lockstep::new(Box::new(Simulated::new(far, context.conditions.clone(), seed+1)), 1, 0)));
+        } else {
+            //the other side of a network match wouldn't have the same file
+            game.tuning = context.tuning;
+        }
+
+        self.game = Some(game);
+        context.audio.start_music();
+        show_mouse(context.settings.gameplay.crosshair == Crosshair::System);
+    }
+
+    //leaving early, however it happens, tells anyone on the other end
+    fn exit(&mut self, context: &mut Context) {
+        if let Some(ref mut game) = self.game {
+            game.forfeit();
+        }
+        context.audio.stop_music();
+        show_mouse(true);
+    }
+
+    fn tick(&mut self, context: &mut Context) -> Transition {
+        clear_background(BLACK);
+
+        let mut game = match self.game.take() {
+            Some(game) => game,
+            None => return Transition::None,
+        };
+
+        //escape opens the pause menu and backing out of it resumes
+        if self.pause.is_some() && back_pressed() {
+            self.set_paused(context, false);
+            context.audio.play_ui(Cue::Click);
+        } else if self.pause.is_none() && is_key_pressed(KeyCode::Escape) && game.player.game.is_none() {
+            self.set_paused(context, true);
+            context.audio.play_ui(Cue::Click);
+        }
+
+        let over = game.tick(&mut context.audio, self.pause.is_some());
+        if let Some(ref mut peer) = self.peer {
+            peer.tick(get_time());
+        }
+        let mut transition = if over {self.results(&game)} else {Transition::None};
+
+        if let Some(ref mut menu) = self.pause {
+            //whatever was changed in settings takes effect on the way back
+            game.apply_settings(&context.settings);
+
+            let top = screen_height()/3.0+40.0;
+            set_default_camera();
+            draw_rectangle(0.0, 0.0, screen_width(), screen_height(), Color::new(0.0, 0.0, 0.0, 0.6));
+            draw_heading("PAUSED", screen_height()/3.0-60.0);
+            if let (Some(_), Some(ref net)) = (&self.peer, &game.net) {
+                let status = match (net.desync, &net.report) {
+                    (Some(tick), Some(_)) => format!("Desync at tick {}, report written", tick),
+                    (Some(tick), None) => format!("Desync at tick {}", tick),
+                    (None, _) => format!("In sync, {} ticks checked", net.verified),
+                };
+                let text_center = get_text_center(&status, None, 30, 1.0, 0.0);
+                draw_text(&status, screen_width()/2.0-text_center.x, screen_height()/3.0-10.0, 30.0, if net.desync.is_some() {RED} else {LIGHTGRAY});
+            }
+
+            let action = menu.update(top);
+            menu.draw(top);
+
+            match action {
+                Some(Action::Moved) => context.audio.play_ui(Cue::Switch),
+                Some(Action::Choose(index)) => {
+                    context.audio.play_ui(Cue::Click);
+                    match menu.items[index].as_str() {
+                        "Resume" => self.set_paused(context, false),
+                        "Settings" => transition = Transition::Push(Box::new(SettingsMenu::new())),
+                        NETWORK_ITEM => transition = Transition::Push(Box::new(NetworkMenu::new(context.conditions.get()))),
+                        "Forfeit" => {
+                            game.forfeit();
+                            transition = self.results(&game);
+                        },
+                        "Quit to menu" => transition = Transition::Reset(Box::new(MainMenu::new())),
+                        _ => transition = Transition::Push(Box::new(ConfirmQuit::new())),
+                    }
+                },
+                _ => {},
+            }
+        }
+
+        self.game = Some(game);
+        transition
+    }
+
+    //a networked match can't wait on a menu stacked over it, so it keeps stepping with the player paused
+    fn covered(&mut self, context: &mut Context) {
+        if let Some(ref mut game) = self.game {
+            if game.networked() {
+                game.tick(&mut context.audio, true);
+            }
+        }
+        if let Some(ref mut peer) = self.peer {
+            peer.tick(get_time());
+        }
+    }
+}
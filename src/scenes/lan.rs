@@ -0,0 +1,202 @@
+use std::net::{IpAddr, SocketAddr};
+
+use macroquad::prelude::*;
+
+use crate::audio::Cue;
+use crate::net::{Discovery, Host, Join, Lockstep, PORT};
+use crate::{Game, Setup};
+
+use super::game::InGame;
+use super::widgets::{draw_heading, Action, Menu};
+use super::{Context, Scene, Transition};
+
+enum State {
+    //picking a host from the lan or typing one in
+    Browsing,
+    //waiting for someone to join a match with this seed
+    Hosting(Host, u64),
+    Joining(Join, SocketAddr),
+}
+
+//a duel on the local network without any server, one side hosts and the other finds it or types its address
+pub struct LanMenu {
+    state: State,
+    menu: Menu,
+    discovery: Option<Discovery>,
+    //typed in for a direct connect, an ip with or without a port
+    address: String,
+    //what went wrong last, shown until the next try
+    message: Option<String>,
+}
+
+impl LanMenu {
+    pub fn new() -> Self {
+        Self {
+            state: State::Browsing,
+            menu: Menu::new(&[]),
+            discovery: None,
+            address: String::new(),
+            message: None,
+        }
+    }
+
+    fn relabel(&mut self) {
+        self.menu.items = match self.state {
+            State::Browsing => {
+                let mut items = vec!["Host".to_string(), format!("Join  {}", if self.address.is_empty() {"type an address"} else {&self.address})];
+                if let Some(ref discovery) = self.discovery {
+                    for (name, address, _) in discovery.hosts.iter() {
+                        items.push(format!("{}  {}", name, address));
+                    }
+                }
+                items.push("Back".to_string());
+                items
+            },
+            _ => vec!["Cancel".to_string()],
+        };
+        self.menu.selected = self.menu.selected.min(self.menu.items.len()-1);
+    }
+
+    fn join(&mut self, address: SocketAddr) {
+        match Join::new(address) {
+            Ok(join) => {
+                self.state = State::Joining(join, address);
+                self.menu.selected = 0;
+            },
+            Err(error) => self.message = Some(format!("Couldn't join: {}", error)),
+        }
+    }
+
+    //an ip on its own goes to the usual port
+    fn typed_address(&self) -> Option<SocketAddr> {
+        self.address.parse::<SocketAddr>().ok().or_else(|| self.address.parse::<IpAddr>().ok().map(|ip| SocketAddr::new(ip, PORT)))
+    }
+}
+
+impl Scene for LanMenu {
+    fn enter(&mut self, _context: &mut Context) {
+        match Discovery::new() {
+            Ok(discovery) => self.discovery = Some(discovery),
+            Err(error) => self.message = Some(format!("Can't search the network: {}", error)),
+        }
+    }
+
+    fn tick(&mut self, context: &mut Context) -> Transition {
+        let sw = screen_width();
+        let sh = screen_height();
+        let top = sh/3.0+60.0;
+        let now = get_time();
+
+        //whatever was typed this frame, only digits and separators so it can't fight the menu's keys
+        let typing = matches!(self.state, State::Browsing) && self.menu.selected == 1;
+        while let Some(character) = get_char_pressed() {
+            if typing && (character.is_ascii_digit() || character == '.' || character == ':') {
+                self.address.push(character);
+            }
+        }
+        let erase = typing && !self.address.is_empty() && is_key_pressed(KeyCode::Backspace);
+        if erase {
+            self.address.pop();
+        }
+
+        let mut transition = Transition::None;
+        match self.state {
+            State::Browsing => {
+                if let Some(ref mut discovery) = self.discovery {
+                    discovery.poll(now);
+                }
+            },
+            State::Hosting(ref mut host, seed) => {
+                if let Some(udp) = host.poll() {
+                    transition = Transition::Replace(Box::new(InGame::online(Setup::default(), seed, 0, Lockstep::hosted(Box::new(udp), 0, 1))));
+                }
+            },
+            State::Joining(ref mut join, _) => {
+                if let Some((seed, udp)) = join.poll(now) {
+                    transition = Transition::Replace(Box::new(InGame::online(Setup::default(), seed, 1, Lockstep::hosted(Box::new(udp), 1, 0))));
+                }
+            },
+        }
+        if !matches!(transition, Transition::None) {
+            context.audio.play_ui(Cue::Click);
+            return transition;
+        }
+        self.relabel();
+
+        set_default_camera();
+        clear_background(BLACK);
+        draw_heading("LAN", sh/6.0);
+
+        let status = match self.state {
+            State::Browsing => match self.discovery {
+                Some(ref discovery) if discovery.hosts.is_empty() => "Looking for hosts on the network...".to_string(),
+                Some(_) => "Hosts on the network".to_string(),
+                None => String::new(),
+            },
+            State::Hosting(ref host, _) => format!("Waiting for a player on port {}", host.port()),
+            State::Joining(_, address) => format!("Joining {}...", address),
+        };
+        for (line, (text, color)) in [(status, LIGHTGRAY), (self.message.clone().unwrap_or_default(), RED)].iter().enumerate() {
+            let text_center = get_text_center(text, None, 30, 1.0, 0.0);
+            draw_text(text, sw/2.0-text_center.x, sh/6.0+90.0+line as f32*40.0, 30.0, *color);
+        }
+
+        let action = self.menu.update(top);
+        self.menu.draw(top);
+
+        let back = is_key_pressed(KeyCode::Escape) || (is_key_pressed(KeyCode::Backspace) && !typing);
+        let chosen = match action {
+            Some(Action::Moved) => {
+                context.audio.play_ui(Cue::Switch);
+                None
+            },
+            Some(Action::Choose(index)) => {
+                context.audio.play_ui(Cue::Click);
+                Some(index)
+            },
+            _ => None,
+        };
+
+        match self.state {
+            State::Browsing if back => return Transition::Pop,
+            State::Browsing => {
+                let hosts = self.discovery.as_ref().map_or(0, |discovery| discovery.hosts.len());
+                match chosen {
+                    Some(0) => {
+                        self.message = None;
+                        let seed = Game::clock_seed();
+                        match Host::new(PORT, seed) {
+                            Ok(host) => {
+                                self.state = State::Hosting(host, seed);
+                                self.menu.selected = 0;
+                            },
+                            Err(error) => self.message = Some(format!("Couldn't host: {}", error)),
+                        }
+                    },
+                    Some(1) => {
+                        self.message = None;
+                        match self.typed_address() {
+                            Some(address) => self.join(address),
+                            None => self.message = Some("That isn't an address, try 192.168.1.20:7777".to_string()),
+                        }
+                    },
+                    Some(index) if index < 2+hosts => {
+                        self.message = None;
+                        let address = self.discovery.as_ref().unwrap().hosts[index-2].1;
+                        self.join(address);
+                    },
+                    Some(_) => return Transition::Pop,
+                    None => {},
+                }
+            },
+            //cancelling drops the sockets, anyone half joined gives up on their own
+            _ if back || chosen.is_some() => {
+                self.state = State::Browsing;
+                self.menu.selected = 0;
+            },
+            _ => {},
+        }
+
+        Transition::None
+    }
+}
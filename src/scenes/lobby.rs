@@ -0,0 +1,65 @@
+use macroquad::prelude::*;
+
+use crate::audio::Cue;
+use crate::Mode;
+
+use super::game::InGame;
+use super::widgets::{back_pressed, draw_heading, Action, Menu};
+use super::{Context, Scene, Transition};
+
+//who's playing and on what before the match starts
+pub struct Lobby {
+    mode: Mode,
+    menu: Menu,
+}
+
+impl Lobby {
+    pub fn new(mode: Mode) -> Self {
+        Self {
+            mode,
+            menu: Menu::new(&["Start", "Back"]),
+        }
+    }
+}
+
+impl Scene for Lobby {
+    fn tick(&mut self, context: &mut Context) -> Transition {
+        let sw = screen_width();
+        let sh = screen_height();
+        let top = sh/2.0+60.0;
+
+        set_default_camera();
+        clear_background(BLACK);
+        draw_heading("LOBBY", sh/6.0);
+
+        draw_text(&format!("Mode: {}", self.mode.name()), sw/2.0-230.0, sh/6.0+90.0, 36.0, WHITE);
+        draw_text("Map: Arena", sw/2.0-230.0, sh/6.0+130.0, 36.0, WHITE);
+        draw_text("You", sw/2.0-230.0, sh/6.0+190.0, 36.0, context.settings.gameplay.palette.ally());
+        draw_text("Training dummy", sw/2.0-230.0, sh/6.0+230.0, 36.0, context.settings.gameplay.palette.enemy());
+        if self.mode == Mode::Practice {
+            draw_text("Press Q in game for more dummies", sw/2.0-230.0, sh/6.0+270.0, 30.0, LIGHTGRAY);
+        }
+
+        let action = self.menu.update(top);
+        self.menu.draw(top);
+
+        if back_pressed() {
+            return Transition::Pop;
+        }
+
+        match action {
+            Some(Action::Moved) => context.audio.play_ui(Cue::Switch),
+            Some(Action::Choose(0)) => {
+                context.audio.play_ui(Cue::Click);
+                return Transition::Replace(Box::new(InGame::new(self.mode)));
+            },
+            Some(Action::Choose(_)) => {
+                context.audio.play_ui(Cue::Click);
+                return Transition::Pop;
+            },
+            _ => {},
+        }
+
+        Transition::None
+    }
+}
@@ -0,0 +1,52 @@
+use macroquad::prelude::*;
+
+use crate::audio::Cue;
+
+use super::lan::LanMenu;
+use super::mode_select::ModeSelect;
+use super::online::OnlineMenu;
+use super::settings_menu::SettingsMenu;
+use super::widgets::{draw_heading, Action, Menu};
+use super::{Context, Scene, Transition};
+
+pub struct MainMenu {
+    menu: Menu,
+}
+
+impl MainMenu {
+    pub fn new() -> Self {
+        Self {
+            menu: Menu::new(&["Play", "Online", "LAN", "Settings", "Quit"]),
+        }
+    }
+}
+
+impl Scene for MainMenu {
+    fn tick(&mut self, context: &mut Context) -> Transition {
+        let top = screen_height()/2.0-60.0;
+
+        set_default_camera();
+        clear_background(BLACK);
+        draw_heading("SKILL DUEL", screen_height()/4.0);
+
+        let action = self.menu.update(top);
+        self.menu.draw(top);
+
+        match action {
+            Some(Action::Moved) => context.audio.play_ui(Cue::Switch),
+            Some(Action::Choose(index)) => {
+                context.audio.play_ui(Cue::Click);
+                return match index {
+                    0 => Transition::Push(Box::new(ModeSelect::new())),
+                    1 => Transition::Push(Box::new(OnlineMenu::new())),
+                    2 => Transition::Push(Box::new(LanMenu::new())),
+                    3 => Transition::Push(Box::new(SettingsMenu::new())),
+                    _ => Transition::Quit,
+                };
+            },
+            _ => {},
+        }
+
+        Transition::None
+    }
+}
@@ -0,0 +1,65 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use crate::assets::Assets;
+use crate::audio::Audio;
+use crate::net::Conditions;
+use crate::settings::Settings;
+use crate::tuning::Tuning;
+
+mod confirm;
+mod game;
+mod lan;
+mod lobby;
+mod main_menu;
+mod mode_select;
+mod network;
+mod online;
+mod results;
+mod settings_menu;
+mod title;
+mod widgets;
+
+pub use title::Title;
+
+//what a scene wants to happen to the stack after this frame
+pub enum Transition {
+    None,
+    Push(Box<dyn Scene>),
+    Pop,
+    Replace(Box<dyn Scene>),
+    //throw the whole stack away and start again from this scene
+    Reset(Box<dyn Scene>),
+    Quit,
+}
+
+//loaded once at startup and handed to whichever scene is on top
+pub struct Context {
+    pub settings: Settings,
+    pub audio: Audio,
+    pub assets: Assets,
+    //from tuning.toml, only matches on this machine alone play by it
+    pub tuning: Tuning,
+    //what the simulated network does to packets, shared with any match using it
+    pub conditions: Rc<Cell<Conditions>>,
+    //--net-test plays duels against a second copy of the game over the simulated network
+    pub net_test: bool,
+}
+
+//one screen of the game. only the top of the stack runs, the ones under it wait until it pops
+pub trait Scene {
+    //called when the scene is put on the stack and when it's taken off for good
+    fn enter(&mut self, _context: &mut Context) {}
+    fn exit(&mut self, _context: &mut Context) {}
+
+    //update and draw one frame
+    fn tick(&mut self, context: &mut Context) -> Transition;
+
+    //runs instead of tick while another scene is on top, for anything that can't wait
+    fn covered(&mut self, _context: &mut Context) {}
+
+    //the window's close button, asks first unless the scene knows better
+    fn quit_requested(&mut self) -> Transition {
+        Transition::Push(Box::new(confirm::ConfirmQuit::new()))
+    }
+}
@@ -0,0 +1,61 @@
+use macroquad::prelude::*;
+
+use crate::audio::Cue;
+use crate::Mode;
+
+use super::lobby::Lobby;
+use super::widgets::{back_pressed, draw_heading, Action, Menu};
+use super::{Context, Scene, Transition};
+
+const MODES: [Mode; 2] = [Mode::Duel, Mode::Practice];
+
+pub struct ModeSelect {
+    menu: Menu,
+}
+
+impl ModeSelect {
+    pub fn new() -> Self {
+        let mut items: Vec<&str> = MODES.iter().map(|mode| mode.name()).collect();
+        items.push("Back");
+
+        Self {
+            menu: Menu::new(&items),
+        }
+    }
+}
+
+impl Scene for ModeSelect {
+    fn tick(&mut self, context: &mut Context) -> Transition {
+        let top = screen_height()/2.0-100.0;
+
+        set_default_camera();
+        clear_background(BLACK);
+        draw_heading("MODE", screen_height()/4.0);
+
+        let action = self.menu.update(top);
+        self.menu.draw(top);
+
+        if let Some(mode) = MODES.get(self.menu.selected) {
+            let text_center = get_text_center(mode.description(), None, 30, 1.0, 0.0);
+            draw_text(mode.description(), screen_width()/2.0-text_center.x, top+240.0-text_center.y, 30.0, LIGHTGRAY);
+        }
+
+        if back_pressed() {
+            return Transition::Pop;
+        }
+
+        match action {
+            Some(Action::Moved) => context.audio.play_ui(Cue::Switch),
+            Some(Action::Choose(index)) => {
+                context.audio.play_ui(Cue::Click);
+                return match MODES.get(index) {
+                    Some(mode) => Transition::Push(Box::new(Lobby::new(*mode))),
+                    None => Transition::Pop,
+                };
+            },
+            _ => {},
+        }
+
+        Transition::None
+    }
+}
@@ -0,0 +1,82 @@
+use macroquad::prelude::*;
+
+use crate::audio::Cue;
+use crate::net::Conditions;
+
+use super::widgets::{back_pressed, draw_heading, Action, Menu};
+use super::{Context, Scene, Transition};
+
+//milliseconds and percent a press moves each one by
+const DELAY_STEP: f32 = 10.0;
+const CHANCE_STEP: f32 = 0.01;
+
+//what the simulated network is doing to a network test, changes go through to the running match straight away
+pub struct NetworkMenu {
+    menu: Menu,
+}
+
+impl NetworkMenu {
+    pub fn new(conditions: Conditions) -> Self {
+        let mut menu = Self {
+            menu: Menu::new(&[]),
+        };
+        menu.relabel(conditions);
+        menu
+    }
+
+    fn relabel(&mut self, conditions: Conditions) {
+        let delay = |name: &str, value: f32| format!("{}  < {:.0} ms >", name, value);
+        let chance = |name: &str, value: f32| format!("{}  < {:.0}% >", name, value*100.0);
+
+        self.menu.items = vec![
+            delay("Latency", conditions.latency),
+            delay("Jitter", conditions.jitter),
+            chance("Loss", conditions.loss),
+            chance("Duplicate", conditions.duplicate),
+            chance("Reorder", conditions.reorder),
+            "Back".to_string(),
+        ];
+    }
+}
+
+impl Scene for NetworkMenu {
+    fn tick(&mut self, context: &mut Context) -> Transition {
+        let top = screen_height()/5.0+60.0;
+
+        set_default_camera();
+        clear_background(BLACK);
+        draw_heading("NETWORK", screen_height()/5.0-40.0);
+
+        let action = self.menu.update(top);
+        self.menu.draw(top);
+
+        if back_pressed() {
+            return Transition::Pop;
+        }
+
+        let mut conditions = context.conditions.get();
+        match action {
+            Some(Action::Moved) => context.audio.play_ui(Cue::Switch),
+            Some(Action::Choose(5)) => {
+                context.audio.play_ui(Cue::Click);
+                return Transition::Pop;
+            },
+            Some(Action::Adjust(index, step)) => {
+                match index {
+                    0 => conditions.latency = (conditions.latency+step*DELAY_STEP).max(0.0),
+                    1 => conditions.jitter = (conditions.jitter+step*DELAY_STEP).max(0.0),
+                    2 => conditions.loss = (conditions.loss+step*CHANCE_STEP).clamp(0.0, 1.0),
+                    3 => conditions.duplicate = (conditions.duplicate+step*CHANCE_STEP).clamp(0.0, 1.0),
+                    4 => conditions.reorder = (conditions.reorder+step*CHANCE_STEP).clamp(0.0, 1.0),
+                    _ => return Transition::None,
+                }
+                context.audio.play_ui(Cue::Switch);
+                context.conditions.set(conditions);
+                self.relabel(conditions);
+            },
+            _ => {},
+        }
+
+        Transition::None
+    }
+}
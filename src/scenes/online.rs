@@ -0,0 +1,210 @@
+use std::cell::RefCell;
+use std::env;
+use std::rc::Rc;
+
+use macroquad::prelude::*;
+
+use crate::audio::Cue;
+use crate::map::MAPS;
+use crate::net::{Backend, Lobby, Nakama, Server};
+use crate::tuning::Ruleset;
+use crate::{Game, Setup};
+
+use super::game::InGame;
+use super::settings_menu::cycle;
+use super::widgets::{draw_heading, Action, Menu};
+use super::{Context, Scene, Transition};
+
+//what lobby codes are made of, anything else typed is ignored. it leaves out the menus' w, a, s and d
+const CODE_LETTERS: &str = "BCEFGHJKLMNPQRTUVXYZ23456789";
+const CODE_LENGTH: usize = 6;
+
+//a private duel through the nakama server, one player makes a lobby and tells the other its code
+pub struct OnlineMenu {
+    lobby: Option<Lobby>,
+    menu: Menu,
+    code: String,
+    message: Option<String>,
+}
+
+impl OnlineMenu {
+    pub fn new() -> Self {
+        Self {
+            lobby: None,
+            menu: Menu::new(&[]),
+            code: String::new(),
+            message: None,
+        }
+    }
+
+    fn backend() -> Rc<RefCell<dyn Backend>> {
+        let arguments: Vec<String> = env::args().collect();
+        Rc::new(RefCell::new(Nakama::connect(&Server::from_args(&arguments), Game::clock_seed())))
+    }
+
+    fn relabel(&mut self) {
+        let choice = |name: &str, value: &str| format!("{}  < {} >", name, value);
+
+        self.menu.items = match self.lobby {
+            None => vec![
+                "Create lobby".to_string(),
+                format!("Join  {}", if self.code.is_empty() {"type a code"} else {&self.code}),
+                "Back".to_string(),
+            ],
+            Some(ref lobby) => {
+                let ready = lobby.ready[if lobby.host {0} else {1}];
+                let mut items = vec![if ready {"Not ready".to_string()} else {"Ready".to_string()}, "Leave".to_string()];
+                if lobby.host {
+                    items.insert(0, choice("Map", MAPS[lobby.setup.map].0));
+                    items.insert(1, choice("Rules", lobby.setup.ruleset.name()));
+                }
+                items
+            },
+        };
+        self.menu.selected = self.menu.selected.min(self.menu.items.len()-1);
+    }
+}
+
+impl Scene for OnlineMenu {
+    fn tick(&mut self, context: &mut Context) -> Transition {
+        let sw = screen_width();
+        let sh = screen_height();
+        let left = sw/2.0-230.0;
+        let now = get_time();
+
+        let typing = self.lobby.is_none() && self.menu.selected == 1;
+        while let Some(character) = get_char_pressed() {
+            let character = character.to_ascii_uppercase();
+            if typing && self.code.len() < CODE_LENGTH && CODE_LETTERS.contains(character) {
+                self.code.push(character);
+            }
+        }
+        let erase = typing && !self.code.is_empty() && is_key_pressed(KeyCode::Backspace);
+        if erase {
+            self.code.pop();
+        }
+
+        if let Some(ref mut lobby) = self.lobby {
+            if let Some((setup, seed, local, net)) = lobby.update(now) {
+                context.audio.play_ui(Cue::Click);
+                return Transition::Replace(Box::new(InGame::online(setup, seed, local, net)));
+            }
+            //a lobby that failed to happen goes back to the start with why
+            if lobby.error.is_some() {
+                self.message = lobby.error.take();
+                if lobby.code.is_none() || !lobby.host {
+                    self.lobby = None;
+                }
+            }
+        }
+        self.relabel();
+
+        set_default_camera();
+        clear_background(BLACK);
+        draw_heading("ONLINE", sh/6.0);
+
+        let mut y = sh/6.0+90.0;
+        match self.lobby {
+            None => {
+                draw_text("Make a lobby and send its code, or join with one", left, y, 30.0, LIGHTGRAY);
+                y += 40.0;
+            },
+            Some(ref lobby) => {
+                let code = match lobby.code {
+                    Some(ref code) => format!("Code: {}", code),
+                    None => "Making a lobby...".to_string(),
+                };
+                draw_text(&code, left, y, 40.0, WHITE);
+                y += 50.0;
+                let players = lobby.players();
+                for (index, (name, ready)) in players.iter().enumerate() {
+                    let color = if (index == 0) == lobby.host {context.settings.gameplay.palette.ally()} else {context.settings.gameplay.palette.enemy()};
+                    draw_text(&format!("{}{}", name, if index == 0 {" (host)"} else {""}), left, y, 36.0, color);
+                    draw_text(if *ready {"Ready"} else {"Not ready"}, left+340.0, y, 36.0, if *ready {GREEN} else {GRAY});
+                    y += 40.0;
+                }
+                if players.len() < 2 {
+                    draw_text("Waiting for someone to join", left, y, 30.0, LIGHTGRAY);
+                    y += 40.0;
+                }
+                if !lobby.host {
+                    draw_text(&format!("Map: {}   Rules: {}", MAPS[lobby.setup.map].0, lobby.setup.ruleset.name()), left, y, 30.0, LIGHTGRAY);
+                    y += 40.0;
+                }
+            },
+        }
+        if let Some(ref message) = self.message {
+            draw_text(message, left, y, 30.0, RED);
+        }
+
+        let top = sh/2.0+40.0;
+        let action = self.menu.update(top);
+        self.menu.draw(top);
+
+        let back = is_key_pressed(KeyCode::Escape) || (is_key_pressed(KeyCode::Backspace) && !typing);
+        let (chosen, step) = match action {
+            Some(Action::Moved) => {
+                context.audio.play_ui(Cue::Switch);
+                (None, 0.0)
+            },
+            Some(Action::Choose(index)) => {
+                context.audio.play_ui(Cue::Click);
+                (Some(index), 1.0)
+            },
+            Some(Action::Adjust(index, step)) => (Some(index), step),
+            None => (None, 0.0),
+        };
+        let adjusting = matches!(action, Some(Action::Adjust(..)));
+
+        match self.lobby {
+            None if back => return Transition::Pop,
+            None => match chosen {
+                Some(0) if !adjusting => {
+                    self.message = None;
+                    self.lobby = Some(Lobby::create(Self::backend(), Game::clock_seed()));
+                    self.menu.selected = 0;
+                },
+                Some(1) if !adjusting => {
+                    self.message = None;
+                    if self.code.len() == CODE_LENGTH {
+                        self.lobby = Some(Lobby::join(Self::backend(), &self.code));
+                        self.menu.selected = 0;
+                    } else {
+                        self.message = Some(format!("Codes are {} letters and numbers", CODE_LENGTH));
+                    }
+                },
+                Some(2) if !adjusting => return Transition::Pop,
+                _ => {},
+            },
+            //leaving drops the connection, the server tells the other player
+            Some(_) if back => {
+                self.lobby = None;
+                self.message = None;
+                self.menu.selected = 0;
+            },
+            Some(ref mut lobby) => {
+                //the host's list has the map and rules first
+                let index = chosen.map(|index| if lobby.host {index} else {index+2});
+                match index {
+                    Some(0) => {
+                        let maps: Vec<usize> = (0..MAPS.len()).collect();
+                        lobby.choose(Setup { map: cycle(&maps, lobby.setup.map, step), ..lobby.setup });
+                        context.audio.play_ui(Cue::Switch);
+                    },
+                    Some(1) => {
+                        lobby.choose(Setup { ruleset: cycle(&Ruleset::ALL, lobby.setup.ruleset, step), ..lobby.setup });
+                        context.audio.play_ui(Cue::Switch);
+                    },
+                    Some(2) if !adjusting => lobby.toggle_ready(),
+                    Some(3) if !adjusting => {
+                        self.lobby = None;
+                        self.menu.selected = 0;
+                    },
+                    _ => {},
+                }
+            },
+        }
+
+        Transition::None
+    }
+}
@@ -0,0 +1,96 @@
+use macroquad::prelude::*;
+
+use crate::audio::Cue;
+use crate::stats::Stats;
+use crate::Mode;
+
+use super::game::InGame;
+use super::main_menu::MainMenu;
+use super::widgets::{back_pressed, draw_heading, Action, Menu};
+use super::{Context, Scene, Transition};
+
+const COLUMNS: [&str; 8] = ["", "Kills", "Deaths", "Shots", "Hits", "Damage", "Gold", "Pickups"];
+
+//the scoreboard after a match
+pub struct Results {
+    mode: Mode,
+    won: bool,
+    local: u32,
+    stats: Stats,
+    //the match's seed, enough to play it again when something went wrong
+    seed: u64,
+    //against someone on another machine rather than dummies. there's no rematch, the connection's gone
+    networked: bool,
+    menu: Menu,
+}
+
+impl Results {
+    pub fn new(mode: Mode, won: bool, local: u32, stats: Stats, seed: u64, networked: bool) -> Self {
+        Self {
+            mode,
+            won,
+            local,
+            stats,
+            seed,
+            networked,
+            menu: Menu::new(if networked {&["Main Menu"][..]} else {&["Rematch", "Main Menu"][..]}),
+        }
+    }
+}
+
+impl Scene for Results {
+    fn tick(&mut self, context: &mut Context) -> Transition {
+        let sw = screen_width();
+        let sh = screen_height();
+        let top = sh-200.0;
+        let left = sw/2.0-480.0;
+
+        set_default_camera();
+        clear_background(BLACK);
+        draw_heading(if self.won {"VICTORY"} else {"DEFEAT"}, sh/6.0);
+
+        for (column, heading) in COLUMNS.iter().enumerate() {
+            draw_text(heading, left+column as f32*120.0, sh/6.0+100.0, 30.0, LIGHTGRAY);
+        }
+
+        for (row, (id, player)) in self.stats.players.iter().enumerate() {
+            let y = sh/6.0+150.0+row as f32*40.0;
+            let name = match *id {
+                id if id == self.local => "You".to_string(),
+                id if self.networked => format!("Player {}", id),
+                id => format!("Dummy {}", id),
+            };
+            let color = if *id == self.local {context.settings.gameplay.palette.ally()} else {context.settings.gameplay.palette.enemy()};
+            let values = [player.kills, player.deaths, player.shots, player.hits, player.damage.round() as u32, player.gold, player.pickups];
+
+            draw_text(&name, left, y, 30.0, color);
+            for (column, value) in values.iter().enumerate() {
+                draw_text(&format!("{}", value), left+(column+1) as f32*120.0, y, 30.0, WHITE);
+            }
+        }
+
+        draw_text(&format!("Seed {}", self.seed), 20.0, sh-20.0, 24.0, GRAY);
+
+        let action = self.menu.update(top);
+        self.menu.draw(top);
+
+        if back_pressed() {
+            return Transition::Reset(Box::new(MainMenu::new()));
+        }
+
+        match action {
+            Some(Action::Moved) => context.audio.play_ui(Cue::Switch),
+            Some(Action::Choose(index)) => {
+                context.audio.play_ui(Cue::Click);
+                return if self.menu.items[index] == "Rematch" {
+                    Transition::Replace(Box::new(InGame::new(self.mode)))
+                } else {
+                    Transition::Reset(Box::new(MainMenu::new()))
+                };
+            },
+            _ => {},
+        }
+
+        Transition::None
+    }
+}
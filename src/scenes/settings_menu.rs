@@ -0,0 +1,280 @@
+use macroquad::prelude::*;
+
+use crate::audio::Cue;
+use crate::input::{Binding, Bindings};
+use crate::settings::{Crosshair, Palette, Settings, MINIMAP_SIZES, RESOLUTIONS, SAMPLE_COUNTS};
+
+use super::widgets::{back_pressed, draw_heading, Action, Menu};
+use super::{Context, Scene, Transition};
+
+const VOLUME_STEP: f32 = 0.1;
+
+#[derive(Clone, Copy)]
+enum Page {
+    Video,
+    Audio,
+    Controls,
+    Gameplay,
+}
+
+const PAGES: [Page; 4] = [Page::Video, Page::Audio, Page::Controls, Page::Gameplay];
+
+impl Page {
+    fn name(&self) -> &'static str {
+        match self {
+            Page::Video => "VIDEO",
+            Page::Audio => "AUDIO",
+            Page::Controls => "CONTROLS",
+            Page::Gameplay => "GAMEPLAY",
+        }
+    }
+}
+
+//the step after current in all, wrapping both ways
+pub fn cycle<T: Copy + PartialEq>(all: &[T], current: T, step: f32) -> T {
+    let index = all.iter().position(|item| *item == current).unwrap_or(0);
+    let count = all.len();
+    all[if step < 0.0 {(index+count-1)%count} else {(index+1)%count}]
+}
+
+//picks which page to open, the settings are saved whenever a page closes
+pub struct SettingsMenu {
+    menu: Menu,
+}
+
+impl SettingsMenu {
+    pub fn new() -> Self {
+        Self {
+            menu: Menu::new(&["Video", "Audio", "Controls", "Gameplay", "Back"]),
+        }
+    }
+}
+
+impl Scene for SettingsMenu {
+    fn tick(&mut self, context: &mut Context) -> Transition {
+        let top = screen_height()/5.0+60.0;
+
+        set_default_camera();
+        clear_background(BLACK);
+        draw_heading("SETTINGS", screen_height()/5.0-40.0);
+
+        let action = self.menu.update(top);
+        self.menu.draw(top);
+
+        if back_pressed() {
+            return Transition::Pop;
+        }
+
+        match action {
+            Some(Action::Moved) => context.audio.play_ui(Cue::Switch),
+            Some(Action::Choose(index)) if index < PAGES.len() => {
+                context.audio.play_ui(Cue::Click);
+                return Transition::Push(Box::new(SettingsPage::new(PAGES[index], &context.settings)));
+            },
+            Some(Action::Choose(_)) => {
+                context.audio.play_ui(Cue::Click);
+                return Transition::Pop;
+            },
+            _ => {},
+        }
+
+        Transition::None
+    }
+}
+
+//sliders and choices move with left and right, toggles flip with anything. changes apply straight away
+struct SettingsPage {
+    page: Page,
+    menu: Menu,
+    //the binding waiting on a key press
+    waiting: Option<usize>,
+}
+
+impl SettingsPage {
+    fn new(page: Page, settings: &Settings) -> Self {
+        let mut menu = Self {
+            page,
+            menu: Menu::new(&[]),
+            waiting: None,
+        };
+        menu.relabel(settings);
+        menu
+    }
+
+    fn relabel(&mut self, settings: &Settings) {
+        let volume = |name: &str, value: f32| format!("{}  < {:.0}% >", name, value*100.0);
+        let toggle = |name: &str, value: bool| format!("{}  {}", name, if value {"On"} else {"Off"});
+        let choice = |name: &str, value: &str| format!("{}  < {} >", name, value);
+
+        let mut items = match self.page {
+            Page::Video => {
+                let video = &settings.video;
+                vec![
+                    choice("Resolution", &format!("{}x{}", video.width, video.height)),
+                    toggle("Fullscreen", video.fullscreen),
+                    toggle("VSync*", video.vsync),
+                    choice("Anti-aliasing*", &if video.msaa > 1 {format!("{}x", video.msaa)} else {"Off".to_string()}),
+                    toggle("High DPI*", video.high_dpi),
+                ]
+            },
+            Page::Audio => vec![
+                volume("Master", settings.volumes.master),
+                volume("Effects", settings.volumes.sfx),
+                volume("Music", settings.volumes.music),
+                volume("Interface", settings.volumes.ui),
+            ],
+            Page::Controls => {
+                let mut items: Vec<String> = Bindings::NAMES.iter().enumerate().map(|(index, name)| {
+                    if self.waiting == Some(index) {
+                        format!("{}  press a key", name)
+                    } else {
+                        format!("{}  {}", name, settings.controls.get(index).name())
+                    }
+                }).collect();
+                items.push("Reset to defaults".to_string());
+                items
+            },
+            Page::Gameplay => {
+                let gameplay = &settings.gameplay;
+                vec![
+                    toggle("Hit markers", gameplay.hit_markers),
+                    toggle("Damage numbers", gameplay.damage_numbers),
+                    toggle("Damage direction", gameplay.damage_indicators),
+                    toggle("Screen shake", gameplay.screen_shake),
+                    choice("Crosshair", gameplay.crosshair.name()),
+                    choice("Colours", gameplay.palette.name()),
+                    choice("Minimap", match MINIMAP_SIZES.iter().position(|size| *size == gameplay.minimap_size) {
+                        Some(0) => "Off",
+                        Some(1) => "Small",
+                        Some(2) => "Medium",
+                        Some(_) => "Large",
+                        None => "Custom",
+                    }),
+                    volume("Minimap opacity", gameplay.minimap_opacity),
+                    toggle("Rotate minimap", gameplay.minimap_rotate),
+                ]
+            },
+        };
+        items.push("Back".to_string());
+
+        self.menu.items = items;
+    }
+
+    //true if a choice or toggle moved, sliders included
+    fn change(&self, settings: &mut Settings, index: usize, step: f32) -> bool {
+        match (self.page, index) {
+            (Page::Video, 0) => {
+                let video = &mut settings.video;
+                let (width, height) = cycle(&RESOLUTIONS, (video.width, video.height), step);
+                video.width = width;
+                video.height = height;
+                if !video.fullscreen {
+                    request_new_screen_size(width as f32, height as f32);
+                }
+            },
+            (Page::Video, 1) => {
+                let video = &mut settings.video;
+                video.fullscreen = !video.fullscreen;
+                set_fullscreen(video.fullscreen);
+                if !video.fullscreen {
+                    request_new_screen_size(video.width as f32, video.height as f32);
+                }
+            },
+            (Page::Video, 2) => settings.video.vsync = !settings.video.vsync,
+            (Page::Video, 3) => settings.video.msaa = cycle(&SAMPLE_COUNTS, settings.video.msaa, step),
+            (Page::Video, 4) => settings.video.high_dpi = !settings.video.high_dpi,
+
+            (Page::Audio, 0) => settings.volumes.master = (settings.volumes.master+step*VOLUME_STEP).clamp(0.0, 1.0),
+            (Page::Audio, 1) => settings.volumes.sfx = (settings.volumes.sfx+step*VOLUME_STEP).clamp(0.0, 1.0),
+            (Page::Audio, 2) => settings.volumes.music = (settings.volumes.music+step*VOLUME_STEP).clamp(0.0, 1.0),
+            (Page::Audio, 3) => settings.volumes.ui = (settings.volumes.ui+step*VOLUME_STEP).clamp(0.0, 1.0),
+
+            (Page::Gameplay, 0) => settings.gameplay.hit_markers = !settings.gameplay.hit_markers,
+            (Page::Gameplay, 1) => settings.gameplay.damage_numbers = !settings.gameplay.damage_numbers,
+            (Page::Gameplay, 2) => settings.gameplay.damage_indicators = !settings.gameplay.damage_indicators,
+            (Page::Gameplay, 3) => settings.gameplay.screen_shake = !settings.gameplay.screen_shake,
+            (Page::Gameplay, 4) => settings.gameplay.crosshair = cycle(&Crosshair::ALL, settings.gameplay.crosshair, step),
+            (Page::Gameplay, 5) => settings.gameplay.palette = cycle(&Palette::ALL, settings.gameplay.palette, step),
+            (Page::Gameplay, 6) => settings.gameplay.minimap_size = cycle(&MINIMAP_SIZES, settings.gameplay.minimap_size, step),
+            (Page::Gameplay, 7) => settings.gameplay.minimap_opacity = (settings.gameplay.minimap_opacity+step*VOLUME_STEP).clamp(0.0, 1.0),
+            (Page::Gameplay, 8) => settings.gameplay.minimap_rotate = !settings.gameplay.minimap_rotate,
+
+            _ => return false,
+        }
+
+        true
+    }
+}
+
+impl Scene for SettingsPage {
+    fn exit(&mut self, context: &mut Context) {
+        context.settings.save();
+    }
+
+    fn tick(&mut self, context: &mut Context) -> Transition {
+        let top = screen_height()/5.0+60.0;
+        let back = self.menu.items.len()-1;
+
+        set_default_camera();
+        clear_background(BLACK);
+        draw_heading(self.page.name(), screen_height()/5.0-40.0);
+        if let Page::Video = self.page {
+            let note = "* applies after a restart";
+            let text_center = get_text_center(note, None, 26, 1.0, 0.0);
+            draw_text(note, screen_width()/2.0-text_center.x, screen_height()/5.0+20.0, 26.0, LIGHTGRAY);
+        }
+
+        //rebinding swallows the next key, escape cancels
+        if let Some(index) = self.waiting {
+            self.menu.draw(top);
+
+            if back_pressed() {
+                self.waiting = None;
+            } else if let Some(key) = get_last_key_pressed() {
+                if Binding::bindable(key) {
+                    context.settings.controls.rebind(index, key);
+                    self.waiting = None;
+                    context.audio.play_ui(Cue::Click);
+                }
+            }
+            self.relabel(&context.settings);
+
+            return Transition::None;
+        }
+
+        let action = self.menu.update(top);
+        self.menu.draw(top);
+
+        if back_pressed() {
+            return Transition::Pop;
+        }
+
+        match action {
+            Some(Action::Moved) => context.audio.play_ui(Cue::Switch),
+            Some(Action::Choose(index)) if index == back => {
+                context.audio.play_ui(Cue::Click);
+                return Transition::Pop;
+            },
+            Some(Action::Choose(index)) => {
+                match self.page {
+                    Page::Controls if index < Bindings::NAMES.len() => self.waiting = Some(index),
+                    Page::Controls => context.settings.controls = Bindings::default(),
+                    //volumes only move sideways, enter on one does nothing
+                    Page::Audio => {},
+                    _ => {
+                        self.change(&mut context.settings, index, 1.0);
+                    },
+                }
+                context.audio.play_ui(Cue::Click);
+            },
+            Some(Action::Adjust(index, step)) if self.change(&mut context.settings, index, step) => {
+                context.audio.volumes = context.settings.volumes;
+                context.audio.play_ui(Cue::Click);
+            },
+            _ => {},
+        }
+        self.relabel(&context.settings);
+
+        Transition::None
+    }
+}
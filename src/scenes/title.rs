@@ -0,0 +1,40 @@
+use macroquad::prelude::*;
+
+use crate::assets::TextureId;
+use crate::audio::Cue;
+
+use super::main_menu::MainMenu;
+use super::{Context, Scene, Transition};
+
+//the logo and a bobbing play button, anything goes through to the main menu
+pub struct Title {
+    tick: u32,
+}
+
+impl Title {
+    pub fn new() -> Self {
+        Self {
+            tick: 0,
+        }
+    }
+}
+
+impl Scene for Title {
+    fn tick(&mut self, context: &mut Context) -> Transition {
+        let sw = screen_width();
+        let sh = screen_height();
+
+        set_default_camera();
+        clear_background(WHITE);
+        draw_texture(context.assets.textures.get(TextureId::Logo), sw/2.0-240.0, sh/2.0-340.0, WHITE);
+        draw_texture(context.assets.textures.get(TextureId::PlayButton), sw/2.0-80.0, sh/2.0+80.0+(self.tick as f32/15.0).cos()*15.0, WHITE);
+        self.tick += 1;
+
+        if is_mouse_button_released(MouseButton::Left) || is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Space) {
+            context.audio.play_ui(Cue::Click);
+            return Transition::Reset(Box::new(MainMenu::new()));
+        }
+
+        Transition::None
+    }
+}
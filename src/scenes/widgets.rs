@@ -0,0 +1,97 @@
+use macroquad::prelude::*;
+
+const BUTTON_WIDTH: f32 = 460.0;
+const BUTTON_HEIGHT: f32 = 56.0;
+const BUTTON_GAP: f32 = 14.0;
+
+pub enum Action {
+    //the highlight moved, worth a click sound
+    Moved,
+    Choose(usize),
+    //left or right on an item, for sliders and toggles
+    Adjust(usize, f32),
+}
+
+//a column of buttons centred on the screen. the keyboard moves a highlight and the mouse follows it,
+//macroquad has no gamepad input so the arrow keys stand in for a d-pad
+pub struct Menu {
+    pub items: Vec<String>,
+    pub selected: usize,
+}
+
+impl Menu {
+    pub fn new(items: &[&str]) -> Self {
+        Self {
+            items: items.iter().map(|item| item.to_string()).collect(),
+            selected: 0,
+        }
+    }
+
+    //long menus scroll up far enough to keep the highlighted button on screen
+    fn button(&self, index: usize, top: f32) -> Rect {
+        let bottom = top+(self.selected+1) as f32*(BUTTON_HEIGHT+BUTTON_GAP);
+        let scroll = (bottom-screen_height()).max(0.0);
+        Rect::new(screen_width()/2.0-BUTTON_WIDTH/2.0, top-scroll+index as f32*(BUTTON_HEIGHT+BUTTON_GAP), BUTTON_WIDTH, BUTTON_HEIGHT)
+    }
+
+    //top is where the first button starts in screen pixels
+    pub fn update(&mut self, top: f32) -> Option<Action> {
+        let count = self.items.len();
+
+        if is_key_pressed(KeyCode::Up) || is_key_pressed(KeyCode::W) {
+            self.selected = (self.selected+count-1)%count;
+            return Some(Action::Moved);
+        }
+        if is_key_pressed(KeyCode::Down) || is_key_pressed(KeyCode::S) || is_key_pressed(KeyCode::Tab) {
+            self.selected = (self.selected+1)%count;
+            return Some(Action::Moved);
+        }
+        if is_key_pressed(KeyCode::Left) || is_key_pressed(KeyCode::A) {
+            return Some(Action::Adjust(self.selected, -1.0));
+        }
+        if is_key_pressed(KeyCode::Right) || is_key_pressed(KeyCode::D) {
+            return Some(Action::Adjust(self.selected, 1.0));
+        }
+        if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::KpEnter) || is_key_pressed(KeyCode::Space) {
+            return Some(Action::Choose(self.selected));
+        }
+
+        let mouse = Vec2::from(mouse_position());
+        for index in 0..count {
+            if self.button(index, top).contains(mouse) {
+                if is_mouse_button_pressed(MouseButton::Left) {
+                    self.selected = index;
+                    return Some(Action::Choose(index));
+                }
+                if self.selected != index {
+                    self.selected = index;
+                    return Some(Action::Moved);
+                }
+            }
+        }
+
+        None
+    }
+
+    pub fn draw(&self, top: f32) {
+        for (index, item) in self.items.iter().enumerate() {
+            let button = self.button(index, top);
+            let selected = index == self.selected;
+            draw_rectangle(button.x, button.y, button.w, button.h, if selected {BLUE} else {Color::new(1.0, 1.0, 1.0, 0.1)});
+            draw_rectangle_lines(button.x, button.y, button.w, button.h, 3.0, if selected {WHITE} else {GRAY});
+
+            let text_center = get_text_center(item, None, 36, 1.0, 0.0);
+            draw_text(item, button.x+button.w/2.0-text_center.x, button.y+button.h/2.0-text_center.y, 36.0, WHITE);
+        }
+    }
+}
+
+pub fn draw_heading(text: &str, y: f32) {
+    let text_center = get_text_center(text, None, 80, 1.0, 0.0);
+    draw_text(text, screen_width()/2.0-text_center.x, y-text_center.y, 80.0, WHITE);
+}
+
+//escape backs out of every menu
+pub fn back_pressed() -> bool {
+    is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::Backspace)
+}
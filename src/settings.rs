@@ -0,0 +1,244 @@
+use std::fs;
+use std::path::PathBuf;
+
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::audio::Volumes;
+use crate::input::Bindings;
+
+const SETTINGS_DIRECTORY: &str = "skill-duel";
+const SETTINGS_FILE: &str = "settings.toml";
+
+//what the settings screen steps through, a size in the file that isn't one of these loads as the closest
+pub const RESOLUTIONS: [(i32, i32); 5] = [(800, 600), (1280, 720), (1600, 900), (1920, 1080), (2560, 1440)];
+pub const SAMPLE_COUNTS: [i32; 4] = [1, 2, 4, 8];
+//minimap sizes in hud units, 0 hides it
+pub const MINIMAP_SIZES: [f32; 4] = [0.0, 160.0, 240.0, 320.0];
+
+//read before the window opens, only fullscreen and the window size can change while it's up
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Video {
+    pub width: i32,
+    pub height: i32,
+    pub fullscreen: bool,
+    pub vsync: bool,
+    //samples per pixel, 1 is off
+    pub msaa: i32,
+    pub high_dpi: bool,
+}
+
+impl Default for Video {
+    fn default() -> Self {
+        Self {
+            width: 800,
+            height: 600,
+            fullscreen: false,
+            vsync: true,
+            msaa: 1,
+            high_dpi: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Crosshair {
+    //the operating system's own pointer
+    System,
+    Cross,
+    Dot,
+    Circle,
+}
+
+impl Crosshair {
+    pub const ALL: [Crosshair; 4] = [Crosshair::System, Crosshair::Cross, Crosshair::Dot, Crosshair::Circle];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Crosshair::System => "System",
+            Crosshair::Cross => "Cross",
+            Crosshair::Dot => "Dot",
+            Crosshair::Circle => "Circle",
+        }
+    }
+
+    pub fn draw(&self, position: Vec2) {
+        match self {
+            Crosshair::System => {},
+            Crosshair::Cross => {
+                draw_line(position.x-14.0, position.y, position.x-4.0, position.y, 3.0, WHITE);
+                draw_line(position.x+4.0, position.y, position.x+14.0, position.y, 3.0, WHITE);
+                draw_line(position.x, position.y-14.0, position.x, position.y-4.0, 3.0, WHITE);
+                draw_line(position.x, position.y+4.0, position.x, position.y+14.0, 3.0, WHITE);
+            },
+            Crosshair::Dot => {
+                draw_circle(position.x, position.y, 5.0, BLACK);
+                draw_circle(position.x, position.y, 3.0, WHITE);
+            },
+            Crosshair::Circle => {
+                draw_circle_lines(position.x, position.y, 12.0, 2.0, WHITE);
+                draw_circle(position.x, position.y, 1.5, WHITE);
+            },
+        }
+    }
+}
+
+//which two colours tell you apart from everyone else. the default blue and red run together for some players
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Palette {
+    Standard,
+    Deuteranopia,
+    Protanopia,
+    Tritanopia,
+}
+
+impl Palette {
+    pub const ALL: [Palette; 4] = [Palette::Standard, Palette::Deuteranopia, Palette::Protanopia, Palette::Tritanopia];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Palette::Standard => "Standard",
+            Palette::Deuteranopia => "Deuteranopia",
+            Palette::Protanopia => "Protanopia",
+            Palette::Tritanopia => "Tritanopia",
+        }
+    }
+
+    //the local player
+    pub fn ally(&self) -> Color {
+        match self {
+            Palette::Standard => BLUE,
+            Palette::Deuteranopia | Palette::Protanopia => Color::new(0.0, 0.45, 0.7, 1.0),
+            Palette::Tritanopia => Color::new(0.0, 0.6, 0.5, 1.0),
+        }
+    }
+
+    //everyone else
+    pub fn enemy(&self) -> Color {
+        match self {
+            Palette::Standard => RED,
+            Palette::Deuteranopia => Color::new(0.9, 0.6, 0.0, 1.0),
+            Palette::Protanopia => Color::new(0.95, 0.9, 0.25, 1.0),
+            Palette::Tritanopia => Color::new(0.8, 0.2, 0.5, 1.0),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Gameplay {
+    //combat feedback, each can be turned off on its own
+    pub hit_markers: bool,
+    pub damage_numbers: bool,
+    pub damage_indicators: bool,
+    pub screen_shake: bool,
+
+    pub crosshair: Crosshair,
+    pub palette: Palette,
+
+    pub minimap_size: f32,
+    pub minimap_opacity: f32,
+    //turns with the local player's aim instead of staying north up
+    pub minimap_rotate: bool,
+}
+
+impl Default for Gameplay {
+    fn default() -> Self {
+        Self {
+            hit_markers: true,
+            damage_numbers: true,
+            damage_indicators: true,
+            screen_shake: true,
+
+            crosshair: Crosshair::Cross,
+            palette: Palette::Standard,
+
+            minimap_size: 240.0,
+            minimap_opacity: 0.8,
+            minimap_rotate: false,
+        }
+    }
+}
+
+//player preferences that outlive a session, kept as toml in the platform's config directory.
+//missing keys keep their defaults so older files still load
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub video: Video,
+    #[serde(rename = "audio")]
+    pub volumes: Volumes,
+    pub controls: Bindings,
+    pub gameplay: Gameplay,
+}
+
+//falls back to the working directory on platforms without a config directory
+pub fn config_path(file: &str) -> PathBuf {
+    match dirs::config_dir() {
+        Some(directory) => directory.join(SETTINGS_DIRECTORY).join(file),
+        None => PathBuf::from(file),
+    }
+}
+
+impl Settings {
+    fn path() -> PathBuf {
+        config_path(SETTINGS_FILE)
+    }
+
+    pub fn load() -> Self {
+        let path = Self::path();
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(_) => {
+                //first run, leave a file behind to edit
+                let settings = Self::default();
+                settings.save();
+                return settings;
+            }
+        };
+
+        //a broken file is left alone so whatever was in it isn't lost
+        let mut settings: Self = match toml::from_str(&text) {
+            Ok(settings) => settings,
+            Err(error) => {
+                eprintln!("couldn't read {}: {}", path.display(), error);
+                return Self::default();
+            }
+        };
+
+        let volumes = &mut settings.volumes;
+        for volume in [&mut volumes.master, &mut volumes.sfx, &mut volumes.music, &mut volumes.ui] {
+            *volume = volume.clamp(0.0, 1.0);
+        }
+        settings.video.msaa = settings.video.msaa.max(1);
+        //sizes off the list snap to the closest one on it, a zero or huge window won't open
+        let video = &mut settings.video;
+        (video.width, video.height) = RESOLUTIONS.iter().copied()
+            .min_by_key(|(width, height)| width.abs_diff(video.width) as u64+height.abs_diff(video.height) as u64)
+            .unwrap();
+        settings.gameplay.minimap_size = settings.gameplay.minimap_size.max(0.0);
+        settings.gameplay.minimap_opacity = settings.gameplay.minimap_opacity.clamp(0.0, 1.0);
+
+        settings
+    }
+
+    pub fn save(&self) {
+        let path = Self::path();
+        if let Some(directory) = path.parent() {
+            let _ = fs::create_dir_all(directory);
+        }
+
+        let text = match toml::to_string(self) {
+            Ok(text) => text,
+            Err(error) => {
+                eprintln!("couldn't write settings: {}", error);
+                return;
+            }
+        };
+
+        if let Err(error) = fs::write(&path, text) {
+            eprintln!("couldn't save settings to {}: {}", path.display(), error);
+        }
+    }
+}
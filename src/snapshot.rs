@@ -0,0 +1,407 @@
+use macroquad::prelude::*;
+
+use crate::events::GameEvent;
+use crate::stats::{PlayerStats, Stats};
+use crate::visibility::Visibility;
+use crate::{concealed, Class, Entity, Game, Inventory, PickupKind, Weapon, WeaponType, SIGHT_RANGE};
+
+//what a hosted match's server sends its client after each tick, the match as far as that player is allowed to
+//see it. everything goes over as little endian bytes like an input does, anything short or garbled decodes to none
+
+//what kind of entity follows
+const PLAYER: u8 = 0;
+const GOLD: u8 = 1;
+const PROJECTILE: u8 = 2;
+const GRENADE: u8 = 3;
+const PICKUP: u8 = 4;
+
+//and what kind of event
+const SHOT_FIRED: u8 = 0;
+const HIT: u8 = 1;
+const KILL: u8 = 2;
+const PAUSED: u8 = 3;
+const GOLD_COLLECTED: u8 = 4;
+const PICKUP_TAKEN: u8 = 5;
+const EXPLOSION: u8 = 6;
+const WALL_HIT: u8 = 7;
+const WEAPON_SWITCHED: u8 = 8;
+
+//none goes over as all ones, no id gets that high
+const NONE: u32 = u32::MAX;
+
+//a state as it's read back: its tick, whether the match is decided, the entities, the scoreboard and the final stats
+type Snapshot = (u32, bool, Vec<Entity>, Vec<(u32, u32, bool)>, Option<Stats>);
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn byte(&mut self, value: u8) {
+        self.0.push(value);
+    }
+
+    fn flag(&mut self, value: bool) {
+        self.0.push(value as u8);
+    }
+
+    fn word(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn option(&mut self, value: Option<u32>) {
+        self.word(value.unwrap_or(NONE));
+    }
+
+    fn float(&mut self, value: f32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn vec2(&mut self, value: Vec2) {
+        self.float(value.x);
+        self.float(value.y);
+    }
+
+    //slots are 1 to 6 and the other knife grip is 7, the same as an input
+    fn weapon(&mut self, weapon: WeaponType) {
+        self.byte(match weapon {
+            WeaponType::Knife(false) => 7,
+            weapon => weapon.slot_index() as u8,
+        });
+    }
+
+    fn entity(&mut self, entity: &Entity) {
+        let tag = match entity.class {
+            Class::Player { .. } => PLAYER,
+            Class::Gold(_) => GOLD,
+            Class::Projectile(..) => PROJECTILE,
+            Class::Grenade { .. } => GRENADE,
+            Class::Pickup { .. } => PICKUP,
+            //only ever effects, never in the match
+            Class::Particle(..) | Class::AfterImage(..) => return,
+        };
+        self.byte(tag);
+        self.vec2(entity.position);
+        self.vec2(entity.velocity);
+
+        match entity.class {
+            Class::Player { id, weapon, direction, health, last_dash, armor, damage_boost, speed_boost, inventory, last_attacker, paused } => {
+                self.word(id);
+                self.weapon(weapon.class);
+                for value in [weapon.last_fire, weapon.last_alt_fire, weapon.cook.unwrap_or(NONE), weapon.scoped, weapon.parry, weapon.burst, weapon.heat].iter() {
+                    self.word(*value);
+                }
+                self.flag(weapon.overheated);
+                self.float(direction);
+                self.float(health);
+                self.word(last_dash);
+                self.float(armor);
+                self.word(damage_boost);
+                self.word(speed_boost);
+                self.word(inventory.gold);
+                for taken in inventory.pickups.iter() {
+                    self.word(*taken);
+                }
+                self.option(last_attacker);
+                self.flag(paused);
+            },
+            Class::Gold(tick) => self.float(tick),
+            Class::Projectile(weapon, tick, owner, alt) => {
+                self.weapon(weapon);
+                self.word(tick as u32);
+                self.word(owner);
+                self.flag(alt);
+            },
+            Class::Grenade { fuse, height, lift, owner } => {
+                self.word(fuse);
+                self.float(height);
+                self.float(lift);
+                self.word(owner);
+            },
+            Class::Pickup { kind, respawn, claimant, claim } => {
+                self.byte(kind as u8);
+                self.word(respawn);
+                self.option(claimant);
+                self.word(claim);
+            },
+            Class::Particle(..) | Class::AfterImage(..) => {},
+        }
+    }
+
+    fn event(&mut self, event: &GameEvent) {
+        match *event {
+            GameEvent::ShotFired { player, weapon, alt, position, direction, velocity } => {
+                self.byte(SHOT_FIRED);
+                self.word(player);
+                self.weapon(weapon);
+                self.flag(alt);
+                self.vec2(position);
+                self.float(direction);
+                self.vec2(velocity);
+            },
+            GameEvent::Hit { target, attacker, weapon, damage, position, source } => {
+                self.byte(HIT);
+                self.word(target);
+                self.option(attacker);
+                self.weapon(weapon);
+                self.float(damage);
+                self.vec2(position);
+                self.vec2(source);
+            },
+            GameEvent::Kill { victim, killer, position } => {
+                self.byte(KILL);
+                self.word(victim);
+                self.option(killer);
+                self.vec2(position);
+            },
+            GameEvent::Paused { player, paused } => {
+                self.byte(PAUSED);
+                self.word(player);
+                self.flag(paused);
+            },
+            GameEvent::GoldCollected { player, total } => {
+                self.byte(GOLD_COLLECTED);
+                self.word(player);
+                self.word(total);
+            },
+            GameEvent::PickupTaken { player, kind, position } => {
+                self.byte(PICKUP_TAKEN);
+                self.word(player);
+                self.byte(kind as u8);
+                self.vec2(position);
+            },
+            GameEvent::Explosion { position } => {
+                self.byte(EXPLOSION);
+                self.vec2(position);
+            },
+            GameEvent::WallHit { position } => {
+                self.byte(WALL_HIT);
+                self.vec2(position);
+            },
+            GameEvent::WeaponSwitched { player } => {
+                self.byte(WEAPON_SWITCHED);
+                self.word(player);
+            },
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, length: usize) -> Option<&[u8]> {
+        let bytes = self.bytes.get(self.at..self.at+length)?;
+        self.at += length;
+        Some(bytes)
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        self.take(1).map(|bytes| bytes[0])
+    }
+
+    fn flag(&mut self) -> Option<bool> {
+        self.byte().map(|byte| byte != 0)
+    }
+
+    fn word(&mut self) -> Option<u32> {
+        self.take(4).map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn option(&mut self) -> Option<Option<u32>> {
+        self.word().map(|value| if value == NONE {None} else {Some(value)})
+    }
+
+    fn float(&mut self) -> Option<f32> {
+        self.word().map(f32::from_bits)
+    }
+
+    fn vec2(&mut self) -> Option<Vec2> {
+        Some(Vec2::new(self.float()?, self.float()?))
+    }
+
+    fn weapon(&mut self) -> Option<WeaponType> {
+        match self.byte()? {
+            slot @ 1..=7 => Some(WeaponType::slot(slot as usize)),
+            _ => None,
+        }
+    }
+
+    fn pickup(&mut self) -> Option<PickupKind> {
+        match self.byte()? {
+            0 => Some(PickupKind::Health),
+            1 => Some(PickupKind::Armor),
+            2 => Some(PickupKind::Damage),
+            3 => Some(PickupKind::Speed),
+            _ => None,
+        }
+    }
+
+    fn entity(&mut self) -> Option<Entity> {
+        let tag = self.byte()?;
+        let (position, velocity) = (self.vec2()?, self.vec2()?);
+
+        let class = match tag {
+            PLAYER => {
+                let id = self.word()?;
+                let weapon = Weapon {
+                    class: self.weapon()?,
+                    last_fire: self.word()?,
+                    last_alt_fire: self.word()?,
+                    cook: self.option()?,
+                    scoped: self.word()?,
+                    parry: self.word()?,
+                    burst: self.word()?,
+                    heat: self.word()?,
+                    overheated: self.flag()?,
+                };
+                Class::Player {
+                    id,
+                    weapon,
+                    direction: self.float()?,
+                    health: self.float()?,
+                    last_dash: self.word()?,
+                    armor: self.float()?,
+                    damage_boost: self.word()?,
+                    speed_boost: self.word()?,
+                    inventory: Inventory {
+                        gold: self.word()?,
+                        pickups: [self.word()?, self.word()?, self.word()?, self.word()?],
+                    },
+                    last_attacker: self.option()?,
+                    paused: self.flag()?,
+                }
+            },
+            GOLD => Class::Gold(self.float()?),
+            PROJECTILE => Class::Projectile(self.weapon()?, self.word()? as u16, self.word()?, self.flag()?),
+            GRENADE => Class::Grenade { fuse: self.word()?, height: self.float()?, lift: self.float()?, owner: self.word()? },
+            PICKUP => Class::Pickup { kind: self.pickup()?, respawn: self.word()?, claimant: self.option()?, claim: self.word()? },
+            _ => return None,
+        };
+
+        Some(Entity { position, velocity, class })
+    }
+
+    fn event(&mut self) -> Option<GameEvent> {
+        Some(match self.byte()? {
+            SHOT_FIRED => GameEvent::ShotFired { player: self.word()?, weapon: self.weapon()?, alt: self.flag()?, position: self.vec2()?, direction: self.float()?, velocity: self.vec2()? },
+            HIT => GameEvent::Hit { target: self.word()?, attacker: self.option()?, weapon: self.weapon()?, damage: self.float()?, position: self.vec2()?, source: self.vec2()? },
+            KILL => GameEvent::Kill { victim: self.word()?, killer: self.option()?, position: self.vec2()? },
+            PAUSED => GameEvent::Paused { player: self.word()?, paused: self.flag()? },
+            GOLD_COLLECTED => GameEvent::GoldCollected { player: self.word()?, total: self.word()? },
+            PICKUP_TAKEN => GameEvent::PickupTaken { player: self.word()?, kind: self.pickup()?, position: self.vec2()? },
+            EXPLOSION => GameEvent::Explosion { position: self.vec2()? },
+            WALL_HIT => GameEvent::WallHit { position: self.vec2()? },
+            WEAPON_SWITCHED => GameEvent::WeaponSwitched { player: self.word()? },
+            _ => return None,
+        })
+    }
+}
+
+//whether the viewer gets to hear about an event. anything they took part in, and anything that happened where
+//they can see. sight is none once nothing's hidden from them
+fn reveals(event: &GameEvent, viewer: u32, sight: Option<&Visibility>, entities: &[Entity]) -> bool {
+    let sight = match sight {
+        Some(sight) => sight,
+        None => return true,
+    };
+    let seen = |player: u32| entities.iter().any(|entity| matches!(entity.class, Class::Player { id, .. } if id == player) && !concealed(sight, viewer, entity));
+
+    match *event {
+        GameEvent::ShotFired { player, position, .. } => player == viewer || sight.contains(position),
+        GameEvent::Hit { target, attacker, position, .. } => target == viewer || attacker == Some(viewer) || sight.contains(position),
+        GameEvent::Kill { victim, killer, position } => victim == viewer || killer == Some(viewer) || sight.contains(position),
+        //being paused gives nothing away, and the other side has to know to wait
+        GameEvent::Paused { .. } => true,
+        GameEvent::GoldCollected { player, .. } | GameEvent::WeaponSwitched { player } => seen(player),
+        GameEvent::PickupTaken { player, position, .. } => player == viewer || sight.contains(position),
+        GameEvent::Explosion { position } | GameEvent::WallHit { position } => sight.contains(position),
+    }
+}
+
+pub fn decode_events(bytes: &[u8]) -> Vec<GameEvent> {
+    let mut reader = Reader { bytes, at: 0 };
+    let mut events = Vec::new();
+    while let Some(event) = reader.event() {
+        events.push(event);
+    }
+    events
+}
+
+impl Game {
+    //the state and events of the tick just stepped as the viewer is allowed to see them. once the match is
+    //decided, or the viewer is dead, there's nothing left to hide
+    pub fn serve(&mut self, viewer: u32, events: &[GameEvent]) -> (Vec<u8>, Vec<u8>) {
+        let eye = self.entities.iter().find(|entity| matches!(entity.class, Class::Player { id, .. } if id == viewer)).map(|entity| entity.position);
+        if eye != self.served_visibility.as_ref().map(Visibility::eye) {
+            self.served_visibility = eye.map(|eye| Visibility::new(&self.map, eye, SIGHT_RANGE));
+        }
+        let sight = if self.over {None} else {self.served_visibility.as_ref()};
+        let hidden = |entity: &Entity| sight.is_some_and(|sight| concealed(sight, viewer, entity));
+
+        let mut state = Writer(Vec::new());
+        state.word(self.tick-1);
+        state.flag(self.over);
+        let entities: Vec<&Entity> = self.entities.iter().filter(|entity| !hidden(entity)).collect();
+        state.word(entities.len() as u32);
+        for entity in entities {
+            state.entity(entity);
+        }
+        state.word(self.scoreboard.len() as u32);
+        for (id, gold, paused) in self.scoreboard.iter() {
+            state.word(*id);
+            state.word(*gold);
+            state.flag(*paused);
+        }
+        //the scoreboard's totals would give away every hidden shot, so they only go over at the end
+        state.flag(self.over);
+        if self.over {
+            state.word(self.stats.players.len() as u32);
+            for (id, player) in self.stats.players.iter() {
+                state.word(*id);
+                for value in [player.shots, player.hits, player.kills, player.deaths, player.gold, player.pickups].iter() {
+                    state.word(*value);
+                }
+                state.float(player.damage);
+            }
+        }
+
+        let mut revealed = Writer(Vec::new());
+        for event in events.iter().filter(|event| reveals(event, viewer, sight, &self.entities)) {
+            revealed.event(event);
+        }
+
+        (state.0, revealed.0)
+    }
+
+    //takes on a state the server sent, nothing changes unless all of it reads
+    pub fn apply_snapshot(&mut self, bytes: &[u8]) {
+        let mut reader = Reader { bytes, at: 0 };
+        let mut read = || -> Option<Snapshot> {
+            let (tick, over) = (reader.word()?, reader.flag()?);
+            let entities = (0..reader.word()?).map(|_| reader.entity()).collect::<Option<Vec<Entity>>>()?;
+            let scoreboard = (0..reader.word()?).map(|_| Some((reader.word()?, reader.word()?, reader.flag()?))).collect::<Option<Vec<_>>>()?;
+            let stats = if reader.flag()? {
+                let players = (0..reader.word()?).map(|_| {
+                    let id = reader.word()?;
+                    let [shots, hits, kills, deaths, gold, pickups] = [reader.word()?, reader.word()?, reader.word()?, reader.word()?, reader.word()?, reader.word()?];
+                    Some((id, PlayerStats { shots, hits, damage: reader.float()?, kills, deaths, gold, pickups }))
+                }).collect::<Option<Vec<_>>>()?;
+                Some(Stats { players })
+            } else {
+                None
+            };
+            Some((tick, over, entities, scoreboard, stats))
+        };
+
+        if let Some((tick, over, entities, scoreboard, stats)) = read() {
+            self.tick = tick+1;
+            self.over = over;
+            self.entities = entities;
+            self.scoreboard = scoreboard;
+            if let Some(stats) = stats {
+                self.stats = stats;
+            }
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use crate::events::GameEvent;
+
+#[derive(Clone, Copy, Default)]
+pub struct PlayerStats {
+    pub shots: u32,
+    pub hits: u32,
+    pub damage: f32,
+    pub kills: u32,
+    pub deaths: u32,
+    pub gold: u32,
+    pub pickups: u32,
+}
+
+//running totals for the scoreboard, kept up from the event list like everything else that keeps score
+#[derive(Clone, Default)]
+pub struct Stats {
+    //by player id, in the order they joined
+    pub players: Vec<(u32, PlayerStats)>,
+}
+
+impl Stats {
+    pub fn player(&mut self, id: u32) -> &mut PlayerStats {
+        let index = match self.players.iter().position(|(player, _)| *player == id) {
+            Some(index) => index,
+            None => {
+                self.players.push((id, PlayerStats::default()));
+                self.players.len()-1
+            }
+        };
+
+        &mut self.players[index].1
+    }
+
+    pub fn events(&mut self, events: &[GameEvent]) {
+        for event in events.iter() {
+            match *event {
+                GameEvent::ShotFired { player, .. } => self.player(player).shots += 1,
+                GameEvent::Hit { attacker: Some(attacker), damage, .. } => {
+                    let attacker = self.player(attacker);
+                    attacker.hits += 1;
+                    attacker.damage += damage;
+                },
+                GameEvent::Kill { victim, killer, .. } => {
+                    self.player(victim).deaths += 1;
+                    if let Some(killer) = killer {
+                        self.player(killer).kills += 1;
+                    }
+                },
+                GameEvent::GoldCollected { player, total } => self.player(player).gold = total,
+                GameEvent::PickupTaken { player, .. } => self.player(player).pickups += 1,
+                _ => {},
+            }
+        }
+    }
+}
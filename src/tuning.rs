@@ -0,0 +1,243 @@
+use std::f32::consts::PI;
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use crate::settings::config_path;
+use crate::{PickupKind, WeaponType};
+
+const TUNING_FILE: &str = "tuning.toml";
+
+//gameplay numbers that get tweaked while balancing, kept in one place so a match can run with different values.
+//the defaults can be overridden from tuning.toml next to the settings, missing keys keep theirs
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Tuning {
+    //how far a dash carries the player and over how many ticks
+    pub dash_distance: f32,
+    pub dash_ticks: u32,
+    //ticks after a dash starts before another one is allowed
+    pub dash_cooldown: u32,
+    //ticks after a dash starts that projectiles pass through the player
+    pub dash_immunity: u32,
+
+    //added to a player's velocity each tick while a movement key is held
+    pub acceleration: f32,
+    //a player's velocity is multiplied by this every tick
+    pub friction: f32,
+    //speed past max_speed keeps only overspeed_drag of itself each tick
+    pub max_speed: f32,
+    pub overspeed_drag: f32,
+    //movement multiplier while the sniper is scoped
+    pub scoped_speed: f32,
+    //movement multiplier and recoil per weapon, in weapon slot order
+    pub weapon_speed: [f32; 6],
+    pub weapon_recoil: [f32; 6],
+    //recoil can't push a player faster than this along the shot
+    pub recoil_cap: f32,
+    //ticks between alt fires in weapon slot order, the sniper scope has none
+    pub alt_cooldown: [u32; 6],
+
+    //ticks from the pin being pulled to the bang, and how long cooking takes to reach a full throw
+    pub grenade_fuse: u32,
+    pub grenade_full_charge: u32,
+    //throw speed with no cook and with a full one
+    pub grenade_min_throw: f32,
+    pub grenade_max_throw: f32,
+    //taken off a grenade's lift each tick, and the share of its speed kept on a bounce
+    pub grenade_gravity: f32,
+    pub grenade_restitution: f32,
+    //the blast does full damage in the middle falling off to none at the radius
+    pub grenade_radius: f32,
+    pub grenade_damage: f32,
+    //the underhand alt fire
+    pub grenade_roll_speed: f32,
+
+    //a swing hits the closest player within range and arc either side of the aim
+    pub knife_range: f32,
+    pub knife_arc: f32,
+    pub knife_damage: f32,
+    //damage multiplier from behind
+    pub knife_backstab: f32,
+    //the step forward on a hit, or the shove back when it's parried
+    pub knife_lunge: f32,
+    pub knife_throw_speed: f32,
+    pub knife_throw_damage: f32,
+
+    //gunner burst, shots and ticks between them
+    pub burst_shots: u32,
+    pub burst_gap: u32,
+    //shotgun choked slug
+    pub slug_speed: f32,
+    pub slug_damage: f32,
+    //sprayer heat per shot, overheating at the max, and how far venting it reaches
+    pub sprayer_heat: u32,
+    pub sprayer_max_heat: u32,
+    pub vent_range: f32,
+
+    //ticks a player has to stand on a pickup alone before it's theirs
+    pub pickup_claim: u32,
+    //ticks before a taken pickup comes back, in health, armor, damage, speed order
+    pub pickup_respawn: [u32; 4],
+    pub health_pack: f32,
+    pub armor_pack: f32,
+    pub max_armor: f32,
+    //share of incoming damage armor soaks up while it lasts
+    pub armor_absorb: f32,
+    //how long a boost lasts and how much it multiplies by
+    pub boost_ticks: u32,
+    pub damage_boost: f32,
+    pub speed_boost: f32,
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Self {
+            dash_distance: 350.0,
+            dash_ticks: 8,
+            dash_cooldown: 90,
+            dash_immunity: 12,
+
+            acceleration: 1.0,
+            friction: 0.90,
+            max_speed: 12.0,
+            overspeed_drag: 0.8,
+            scoped_speed: 0.4,
+            //knife, gunner, grenade, shotgun, sprayer, sniper
+            weapon_speed: [1.25, 1.0, 1.0, 0.95, 0.9, 0.75],
+            weapon_recoil: [0.0, 5.0, 10.0, 12.0, 2.0, 1.0],
+            recoil_cap: 14.0,
+            alt_cooldown: [90, 40, 90, 60, 90, 0],
+
+            grenade_fuse: 120,
+            grenade_full_charge: 45,
+            grenade_min_throw: 12.0,
+            grenade_max_throw: 40.0,
+            grenade_gravity: 0.8,
+            grenade_restitution: 0.55,
+            grenade_radius: 300.0,
+            grenade_damage: 50.0,
+            grenade_roll_speed: 25.0,
+
+            knife_range: 130.0,
+            knife_arc: PI/4.0,
+            knife_damage: 25.0,
+            knife_backstab: 2.0,
+            knife_lunge: 12.0,
+            knife_throw_speed: 60.0,
+            knife_throw_damage: 30.0,
+
+            burst_shots: 3,
+            burst_gap: 4,
+            slug_speed: 110.0,
+            slug_damage: 22.0,
+            sprayer_heat: 6,
+            sprayer_max_heat: 100,
+            vent_range: 220.0,
+
+            pickup_claim: 20,
+            pickup_respawn: [600, 900, 1800, 1200],
+            health_pack: 50.0,
+            armor_pack: 50.0,
+            max_armor: 100.0,
+            armor_absorb: 0.6,
+            boost_ticks: 600,
+            damage_boost: 1.5,
+            speed_boost: 1.3,
+        }
+    }
+}
+
+impl Tuning {
+    //written out on first run so there's something to edit. a broken file is left alone and the defaults used
+    pub fn load() -> Self {
+        let path = config_path(TUNING_FILE);
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(_) => {
+                let tuning = Self::default();
+                if let Some(directory) = path.parent() {
+                    let _ = fs::create_dir_all(directory);
+                }
+                if let Ok(text) = toml::to_string(&tuning) {
+                    let _ = fs::write(&path, text);
+                }
+                return tuning;
+            }
+        };
+
+        let mut tuning: Self = match toml::from_str(&text) {
+            Ok(tuning) => tuning,
+            Err(error) => {
+                eprintln!("couldn't read {}: {}", path.display(), error);
+                return Self::default();
+            }
+        };
+
+        //these get divided by
+        tuning.dash_ticks = tuning.dash_ticks.max(1);
+        tuning.pickup_claim = tuning.pickup_claim.max(1);
+        tuning.grenade_fuse = tuning.grenade_fuse.max(1);
+        tuning.grenade_full_charge = tuning.grenade_full_charge.max(1);
+        tuning.burst_gap = tuning.burst_gap.max(1);
+        tuning.sprayer_max_heat = tuning.sprayer_max_heat.max(1);
+
+        tuning
+    }
+
+    pub fn speed_for(&self, class: WeaponType) -> f32 {
+        self.weapon_speed[class.slot_index()-1]
+    }
+
+    pub fn recoil_for(&self, class: WeaponType) -> f32 {
+        self.weapon_recoil[class.slot_index()-1]
+    }
+
+    pub fn alt_cooldown_for(&self, class: WeaponType) -> u32 {
+        self.alt_cooldown[class.slot_index()-1]
+    }
+
+    pub fn respawn_for(&self, kind: PickupKind) -> u32 {
+        self.pickup_respawn[kind as usize]
+    }
+}
+
+//variations on the numbers a lobby host can pick for a match, both sides build the same tuning from it
+#[derive(Clone, Copy, PartialEq)]
+pub enum Ruleset {
+    Standard,
+    //quicker on the feet and off the dash
+    Fast,
+    //pickups come back half as often and boosts don't last as long
+    Scarce,
+}
+
+impl Ruleset {
+    pub const ALL: [Ruleset; 3] = [Ruleset::Standard, Ruleset::Fast, Ruleset::Scarce];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Ruleset::Standard => "Standard",
+            Ruleset::Fast => "Fast",
+            Ruleset::Scarce => "Scarce",
+        }
+    }
+
+    pub fn tuning(&self) -> Tuning {
+        let tuning = Tuning::default();
+        match self {
+            Ruleset::Standard => tuning,
+            Ruleset::Fast => Tuning {
+                acceleration: 1.3,
+                max_speed: 15.0,
+                dash_cooldown: 60,
+                ..tuning
+            },
+            Ruleset::Scarce => Tuning {
+                pickup_respawn: [1200, 1800, 3600, 2400],
+                boost_ticks: 300,
+                ..tuning
+            },
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use std::f32::consts::PI;
+
+use macroquad::prelude::*;
+
+use crate::{MAP_SIZE, MAP_SIZE_X, MAP_SIZE_Y};
+
+//rays either side of each wall corner, so one stops on the corner and the other carries on past it
+const CORNER_NUDGE: f32 = 0.0001;
+//how far the fog is pushed out past the edge of what's visible, well off any screen
+const FOG_DEPTH: f32 = 10000.0;
+
+//everything one player can see from where they stand, a polygon around them cut off by walls.
+//it's star shaped about the eye, which the fog and the point test both lean on
+pub struct Visibility {
+    eye: Vec2,
+    polygon: Vec<Vec2>,
+}
+
+impl Visibility {
+    //looks range units out in a square around the eye, only walls inside it can cast shadows
+    pub fn new(map: &[(f32, bool); MAP_SIZE], eye: Vec2, range: f32) -> Self {
+        let mut angles = Vec::new();
+        for corner in [Vec2::new(-range, -range), Vec2::new(range, -range), Vec2::new(-range, range), Vec2::new(range, range)].iter() {
+            angles.push(corner.y.atan2(corner.x));
+        }
+
+        //rays only go to grid points where walls make a corner, not along a straight face or inside solid wall
+        let wall = |x: usize, y: usize| x > 0 && y > 0 && x <= MAP_SIZE_X && y <= MAP_SIZE_Y && map[(x-1)*MAP_SIZE_X+y-1].1;
+        let first = |position: f32| ((position-range)/50.0).floor().max(0.0) as usize;
+        let last = |position: f32, tiles: usize| (((position+range)/50.0).ceil().max(0.0) as usize).min(tiles);
+        for x in first(eye.x)..=last(eye.x, MAP_SIZE_X) {
+            for y in first(eye.y)..=last(eye.y, MAP_SIZE_Y) {
+                //the four tiles around the point, shifted one so the ones off the top and left edge are open
+                let around = [wall(x, y), wall(x+1, y), wall(x, y+1), wall(x+1, y+1)];
+                let walls = around.iter().filter(|wall| **wall).count();
+                if walls == 0 || walls == 4 || (walls == 2 && around[0] != around[3]) {
+                    continue;
+                }
+                let offset = Vec2::new(x as f32*50.0, y as f32*50.0)-eye;
+                let angle = offset.y.atan2(offset.x);
+                angles.extend_from_slice(&[angle-CORNER_NUDGE, angle, angle+CORNER_NUDGE]);
+            }
+        }
+
+        let mut angles: Vec<f32> = angles.into_iter().map(|angle| if angle > PI {angle-2.0*PI} else if angle < -PI {angle+2.0*PI} else {angle}).collect();
+        angles.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        angles.dedup();
+
+        Self {
+            eye,
+            polygon: angles.iter().map(|angle| cast(map, eye, *angle, range)).collect(),
+        }
+    }
+
+    pub fn eye(&self) -> Vec2 {
+        self.eye
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        //even odd crossings of a ray going right from the point
+        let mut inside = false;
+        for (index, a) in self.polygon.iter().enumerate() {
+            let b = self.polygon[(index+1)%self.polygon.len()];
+            if (a.y > point.y) != (b.y > point.y) && point.x < a.x+(point.y-a.y)/(b.y-a.y)*(b.x-a.x) {
+                inside = !inside;
+            }
+        }
+
+        inside
+    }
+
+    //darkens everything outside the polygon, one quad per edge running out away from the eye
+    pub fn draw_fog(&self, color: Color) {
+        let far = |point: Vec2| self.eye+(point-self.eye).normalize_or_zero()*FOG_DEPTH;
+        for (index, a) in self.polygon.iter().enumerate() {
+            let b = self.polygon[(index+1)%self.polygon.len()];
+            draw_triangle(*a, b, far(b), color);
+            draw_triangle(*a, far(b), far(*a), color);
+        }
+    }
+}
+
+//walks the tiles along the ray and stops on the first wall face, or at the edge of the square
+fn cast(map: &[(f32, bool); MAP_SIZE], eye: Vec2, angle: f32, range: f32) -> Vec2 {
+    let direction = Vec2::new(angle.cos(), angle.sin());
+    let limit = (range/direction.x.abs()).min(range/direction.y.abs());
+
+    let mut tile = ((eye.x/50.0).floor() as i32, (eye.y/50.0).floor() as i32);
+    let step = (direction.x.signum() as i32, direction.y.signum() as i32);
+    //distance along the ray to the next vertical and horizontal grid line, and between them
+    let next = |position: f32, tile: i32, direction: f32| {
+        let edge = if direction > 0.0 {(tile+1) as f32*50.0} else {tile as f32*50.0};
+        if direction == 0.0 {f32::INFINITY} else {(edge-position)/direction}
+    };
+    let mut crossing = (next(eye.x, tile.0, direction.x), next(eye.y, tile.1, direction.y));
+    let delta = (50.0/direction.x.abs(), 50.0/direction.y.abs());
+
+    loop {
+        let distance;
+        if crossing.0 < crossing.1 {
+            distance = crossing.0;
+            tile.0 += step.0;
+            crossing.0 += delta.0;
+        } else {
+            distance = crossing.1;
+            tile.1 += step.1;
+            crossing.1 += delta.1;
+        }
+
+        if distance >= limit {
+            return eye+direction*limit;
+        }
+        let inside = tile.0 >= 0 && tile.1 >= 0 && (tile.0 as usize) < MAP_SIZE_X && (tile.1 as usize) < MAP_SIZE_Y;
+        if inside && map[tile.0 as usize*MAP_SIZE_X+tile.1 as usize].1 {
+            return eye+direction*distance;
+        }
+    }
+}